@@ -79,6 +79,16 @@ const Calendar = () => {
         return formattedDate;
     };
 
+    // A day from check_holidays can carry holidays, user events or eclipses, any of them may be empty
+    const holidayNames = (holiday: any): string[] => [
+        ...(holiday?.holidays ?? []),
+        ...(holiday?.user_events ?? []).map((event: any) => event?.name),
+        ...(holiday?.eclipses ?? []).map((eclipse: any) => {
+            const type = `${eclipse?.type ?? ''}`;
+            return `${type.charAt(0).toUpperCase()}${type.slice(1)} ${eclipse?.body} eclipse (${eclipse?.prayer})`;
+        }),
+    ];
+
     const fetchPrayersRecord = async (date: string) => {
         try {
             const result: PrayerRecord[] = await invoke('get_prayer_data_by_date', { date });
@@ -256,7 +266,7 @@ const Calendar = () => {
                         {hijriHolidayData?.map((holiday: any, index: number) => (
                             <div key={index} className={`flex flex-col ${index > 0 ? '' : 'mt-2'}`}>
                                 <div className='flex flex-col px-4 pb-2'>
-                                    <div className='flex flex-row gap-2 text-md'>{holiday?.holidays.map((h: any, i: number) => <p key={i}>{h}{holiday?.holidays?.length > 0 && i < holiday?.holidays?.length - 1 ? ', ' : ''}</p>)}</div>
+                                    <div className='flex flex-row gap-2 text-md'>{holidayNames(holiday).map((h: string, i: number, names: string[]) => <p key={i}>{h}{i < names.length - 1 ? ', ' : ''}</p>)}</div>
                                     <div className='text-sm font-semilight text-gray-400'>{holiday?.day} {holiday?.month} {holiday?.year}</div>
                                     <div className='text-sm font-semilight text-gray-400'>{handleFormatDate(holiday?.gregorian_date)}</div>
                                 </div>
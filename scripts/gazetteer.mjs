@@ -0,0 +1,53 @@
+// Build src-tauri/data/cities.tsv from the GeoNames dumps
+//
+// Download cities15000.zip (unzipped), admin1CodesASCII.txt and countryInfo.txt from
+// https://download.geonames.org/export/dump/ into one directory, then run
+//   npm run gazetteer -- <directory>
+
+import { readFileSync, writeFileSync } from "node:fs";
+import { dirname, join } from "node:path";
+import { fileURLToPath } from "node:url";
+
+const source = process.argv[2];
+if (!source) {
+  console.error("Usage: npm run gazetteer -- <directory with the GeoNames dumps>");
+  process.exit(1);
+}
+
+const output = join(dirname(fileURLToPath(import.meta.url)), "..", "src-tauri", "data", "cities.tsv");
+
+// read the data lines of a tab separated dump
+const rows = (file) =>
+  readFileSync(join(source, file), "utf8")
+    .split("\n")
+    .filter((line) => line && !line.startsWith("#"))
+    .map((line) => line.split("\t"));
+
+// Country names by ISO code, region names by "country.admin1" code
+const countries = new Map(rows("countryInfo.txt").map((fields) => [fields[0], fields[4]]));
+const regions = new Map(rows("admin1CodesASCII.txt").map((fields) => [fields[0], fields[1]]));
+
+// Keep the columns the app reads, largest cities first
+const clean = (value) => (value || "").replace(/[\t\r\n]/g, " ").trim();
+const cities = rows("cities15000.txt")
+  .filter((fields) => fields.length >= 18 && fields[17])
+  .map((fields) => ({
+    name: clean(fields[1]),
+    asciiName: clean(fields[2]) || clean(fields[1]),
+    region: clean(regions.get(`${fields[8]}.${fields[10]}`)),
+    countryCode: fields[8],
+    country: clean(countries.get(fields[8])),
+    latitude: Number(fields[4]).toFixed(4),
+    longitude: Number(fields[5]).toFixed(4),
+    population: Number(fields[14]) || 0,
+    timezone: fields[17],
+  }))
+  .sort((a, b) => b.population - a.population || a.name.localeCompare(b.name));
+
+const header = "# name\tascii name\tregion\tcountry code\tcountry\tlatitude\tlongitude\tpopulation\ttimezone";
+const lines = cities.map((city) =>
+  [city.name, city.asciiName, city.region, city.countryCode, city.country, city.latitude, city.longitude, city.population, city.timezone].join("\t"),
+);
+
+writeFileSync(output, [header, ...lines].join("\n") + "\n");
+console.log(`Wrote ${cities.length} cities to ${output}`);
@@ -0,0 +1,348 @@
+// Solar and lunar position helpers shared by the prayer time engine
+
+use chrono::{DateTime, TimeZone, Utc};
+
+// Sun position for a given julian day
+pub struct SunPosition {
+    pub declination: f64,
+    pub equation_of_time: f64,
+}
+
+// Ecliptic position structure, longitude and latitude in degrees, distance in km
+pub struct EclipticPosition {
+    pub longitude: f64,
+    pub latitude: f64,
+    pub distance: f64,
+}
+
+// astronomical unit in km
+pub const ASTRONOMICAL_UNIT: f64 = 149597870.7;
+
+// equatorial earth radius in km
+pub const EARTH_EQUATORIAL_RADIUS: f64 = 6378.14;
+
+// altitude of the sun's center at sunrise and sunset, refraction and semidiameter included
+pub const SUN_HORIZON_ALTITUDE: f64 = -0.8333;
+
+// mean synodic month in days
+pub const SYNODIC_MONTH: f64 = 29.530588853;
+
+// julian day of the unix epoch
+const UNIX_EPOCH_JD: f64 = 2440587.5;
+
+// degree based trigonometry
+pub fn dsin(d: f64) -> f64 {
+    d.to_radians().sin()
+}
+
+pub fn dcos(d: f64) -> f64 {
+    d.to_radians().cos()
+}
+
+pub fn dtan(d: f64) -> f64 {
+    d.to_radians().tan()
+}
+
+pub fn darcsin(x: f64) -> f64 {
+    x.asin().to_degrees()
+}
+
+pub fn darccos(x: f64) -> f64 {
+    x.acos().to_degrees()
+}
+
+pub fn darctan2(y: f64, x: f64) -> f64 {
+    y.atan2(x).to_degrees()
+}
+
+pub fn darccot(x: f64) -> f64 {
+    (1.0 / x).atan().to_degrees()
+}
+
+// wrap an angle into 0..360
+pub fn fix_angle(a: f64) -> f64 {
+    a.rem_euclid(360.0)
+}
+
+// wrap an hour value into 0..24
+pub fn fix_hour(h: f64) -> f64 {
+    h.rem_euclid(24.0)
+}
+
+// julian day at 0h UT of the given gregorian date
+pub fn julian_day(year: i32, month: u32, day: u32) -> f64 {
+    let (mut year, mut month) = (year as f64, month as f64);
+    if month <= 2.0 {
+        year -= 1.0;
+        month += 12.0;
+    }
+    let a = (year / 100.0).floor();
+    let b = 2.0 - a + (a / 4.0).floor();
+
+    (365.25 * (year + 4716.0)).floor() + (30.6001 * (month + 1.0)).floor() + day as f64 + b - 1524.5
+}
+
+// compute declination (degrees) and equation of time (hours)
+// see http://aa.usno.navy.mil/faq/docs/SunApprox.php
+pub fn sun_position(jd: f64) -> SunPosition {
+    let d = jd - 2451545.0;
+    let g = fix_angle(357.529 + 0.98560028 * d);
+    let q = fix_angle(280.459 + 0.98564736 * d);
+    let l = fix_angle(q + 1.915 * dsin(g) + 0.020 * dsin(2.0 * g));
+
+    let e = 23.439 - 0.00000036 * d;
+    let ra = darctan2(dcos(e) * dsin(l), dcos(l)) / 15.0;
+
+    // keep the equation of time close to zero when q and ra straddle midnight
+    let eqt = q / 15.0 - fix_hour(ra);
+
+    SunPosition {
+        declination: darcsin(dsin(e) * dsin(l)),
+        equation_of_time: eqt - 24.0 * (eqt / 24.0).round(),
+    }
+}
+
+// instant of a julian day, rounded to the second
+pub fn utc_from_julian(jd: f64) -> Option<DateTime<Utc>> {
+    Utc.timestamp_opt(((jd - UNIX_EPOCH_JD) * 86400.0).round() as i64, 0).single()
+}
+
+// difference between terrestrial and universal time in days, NASA polynomial for 2005-2050
+fn delta_t(jd: f64) -> f64 {
+    let t = (jd - 2451545.0) / 365.25;
+    (62.92 + 0.32217 * t + 0.005589 * t * t) / 86400.0
+}
+
+// julian centuries of terrestrial time since J2000 at a julian day in UT
+fn julian_centuries(jd: f64) -> f64 {
+    (jd + delta_t(jd) - 2451545.0) / 36525.0
+}
+
+// nutation in longitude and true obliquity of the ecliptic, in degrees
+fn nutation(t: f64) -> (f64, f64) {
+    let node = 125.04452 - 1934.136261 * t;
+    let sun = 280.4665 + 36000.7698 * t;
+    let moon = 218.3165 + 481267.8813 * t;
+
+    let longitude = (-17.20 * dsin(node) - 1.32 * dsin(2.0 * sun) - 0.23 * dsin(2.0 * moon) + 0.21 * dsin(2.0 * node)) / 3600.0;
+    let obliquity = 23.439291 - 0.0130042 * t + (9.20 * dcos(node) + 0.57 * dcos(2.0 * sun) + 0.10 * dcos(2.0 * moon) - 0.09 * dcos(2.0 * node)) / 3600.0;
+    (longitude, obliquity)
+}
+
+// apparent geocentric position of the sun at a julian day in UT
+// see Meeus, Astronomical Algorithms, chapter 25
+pub fn sun_ecliptic(jd: f64) -> EclipticPosition {
+    let t = julian_centuries(jd);
+    let mean_longitude = 280.46646 + 36000.76983 * t + 0.0003032 * t * t;
+    let anomaly = 357.52911 + 35999.05029 * t - 0.0001537 * t * t;
+    let eccentricity = 0.016708634 - 0.000042037 * t - 0.0000001267 * t * t;
+    let center = (1.914602 - 0.004817 * t - 0.000014 * t * t) * dsin(anomaly)
+        + (0.019993 - 0.000101 * t) * dsin(2.0 * anomaly)
+        + 0.000289 * dsin(3.0 * anomaly);
+
+    let radius = 1.000001018 * (1.0 - eccentricity * eccentricity) / (1.0 + eccentricity * dcos(anomaly + center));
+    let aberration = -20.4898 / 3600.0 / radius;
+
+    EclipticPosition {
+        longitude: fix_angle(mean_longitude + center + aberration + nutation(t).0),
+        latitude: 0.0,
+        distance: radius * ASTRONOMICAL_UNIT,
+    }
+}
+
+// periodic terms of the moon's longitude and distance: multiples of D, M, M', F,
+// then longitude in 1e-6 degrees and distance in 1e-3 km
+const MOON_LONGITUDE_TERMS: [(i8, i8, i8, i8, f64, f64); 60] = [
+    (0, 0, 1, 0, 6288774.0, -20905355.0),
+    (2, 0, -1, 0, 1274027.0, -3699111.0),
+    (2, 0, 0, 0, 658314.0, -2955968.0),
+    (0, 0, 2, 0, 213618.0, -569925.0),
+    (0, 1, 0, 0, -185116.0, 48888.0),
+    (0, 0, 0, 2, -114332.0, -3149.0),
+    (2, 0, -2, 0, 58793.0, 246158.0),
+    (2, -1, -1, 0, 57066.0, -152138.0),
+    (2, 0, 1, 0, 53322.0, -170733.0),
+    (2, -1, 0, 0, 45758.0, -204586.0),
+    (0, 1, -1, 0, -40923.0, -129620.0),
+    (1, 0, 0, 0, -34720.0, 108743.0),
+    (0, 1, 1, 0, -30383.0, 104755.0),
+    (2, 0, 0, -2, 15327.0, 10321.0),
+    (0, 0, 1, 2, -12528.0, 0.0),
+    (0, 0, 1, -2, 10980.0, 79661.0),
+    (4, 0, -1, 0, 10675.0, -34782.0),
+    (0, 0, 3, 0, 10034.0, -23210.0),
+    (4, 0, -2, 0, 8548.0, -21636.0),
+    (2, 1, -1, 0, -7888.0, 24208.0),
+    (2, 1, 0, 0, -6766.0, 30824.0),
+    (1, 0, -1, 0, -5163.0, -8379.0),
+    (1, 1, 0, 0, 4987.0, -16675.0),
+    (2, -1, 1, 0, 4036.0, -12831.0),
+    (2, 0, 2, 0, 3994.0, -10445.0),
+    (4, 0, 0, 0, 3861.0, -11650.0),
+    (2, 0, -3, 0, 3665.0, 14403.0),
+    (0, 1, -2, 0, -2689.0, -7003.0),
+    (2, 0, -1, 2, -2602.0, 0.0),
+    (2, -1, -2, 0, 2390.0, 10056.0),
+    (1, 0, 1, 0, -2348.0, 6322.0),
+    (2, -2, 0, 0, 2236.0, -9884.0),
+    (0, 1, 2, 0, -2120.0, 5751.0),
+    (0, 2, 0, 0, -2069.0, 0.0),
+    (2, -2, -1, 0, 2048.0, -4950.0),
+    (2, 0, 1, -2, -1773.0, 4130.0),
+    (2, 0, 0, 2, -1595.0, 0.0),
+    (4, -1, -1, 0, 1215.0, -3958.0),
+    (0, 0, 2, 2, -1110.0, 0.0),
+    (3, 0, -1, 0, -892.0, 3258.0),
+    (2, 1, 1, 0, -810.0, 2616.0),
+    (4, -1, -2, 0, 759.0, -1897.0),
+    (0, 2, -1, 0, -713.0, -2117.0),
+    (2, 2, -1, 0, -700.0, 2354.0),
+    (2, 1, -2, 0, 691.0, 0.0),
+    (2, -1, 0, -2, 596.0, 0.0),
+    (4, 0, 1, 0, 549.0, -1423.0),
+    (0, 0, 4, 0, 537.0, -1117.0),
+    (4, -1, 0, 0, 520.0, -1571.0),
+    (1, 0, -2, 0, -487.0, -1739.0),
+    (2, 1, 0, -2, -399.0, 0.0),
+    (0, 0, 2, -2, -381.0, -4421.0),
+    (1, 1, 1, 0, 351.0, 0.0),
+    (3, 0, -2, 0, -340.0, 0.0),
+    (4, 0, -3, 0, 330.0, 0.0),
+    (2, -1, 2, 0, 327.0, 0.0),
+    (0, 2, 1, 0, -323.0, 1165.0),
+    (1, 1, -1, 0, 299.0, 0.0),
+    (2, 0, 3, 0, 294.0, 0.0),
+    (2, 0, -1, -2, 0.0, 8752.0),
+];
+
+// periodic terms of the moon's latitude: multiples of D, M, M', F, then latitude in 1e-6 degrees
+const MOON_LATITUDE_TERMS: [(i8, i8, i8, i8, f64); 30] = [
+    (0, 0, 0, 1, 5128122.0),
+    (0, 0, 1, 1, 280602.0),
+    (0, 0, 1, -1, 277693.0),
+    (2, 0, 0, -1, 173237.0),
+    (2, 0, -1, 1, 55413.0),
+    (2, 0, -1, -1, 46271.0),
+    (2, 0, 0, 1, 32573.0),
+    (0, 0, 2, 1, 17198.0),
+    (2, 0, 1, -1, 9266.0),
+    (0, 0, 2, -1, 8822.0),
+    (2, -1, 0, -1, 8216.0),
+    (2, 0, -2, -1, 4324.0),
+    (2, 0, 1, 1, 4200.0),
+    (2, 1, 0, -1, -3359.0),
+    (2, -1, -1, 1, 2463.0),
+    (2, -1, 0, 1, 2211.0),
+    (2, -1, -1, -1, 2065.0),
+    (0, 1, -1, -1, -1870.0),
+    (4, 0, -1, -1, 1828.0),
+    (0, 1, 0, 1, -1794.0),
+    (0, 0, 0, 3, -1749.0),
+    (0, 1, -1, 1, -1565.0),
+    (1, 0, 0, 1, -1491.0),
+    (0, 1, 1, 1, -1475.0),
+    (0, 1, 1, -1, -1410.0),
+    (0, 1, 0, -1, -1344.0),
+    (1, 0, 0, -1, -1335.0),
+    (0, 0, 3, 1, 1107.0),
+    (4, 0, 0, -1, 1021.0),
+    (4, 0, -1, 1, 833.0),
+];
+
+// apparent geocentric position of the moon at a julian day in UT
+// see Meeus, Astronomical Algorithms, chapter 47
+pub fn moon_ecliptic(jd: f64) -> EclipticPosition {
+    let t = julian_centuries(jd);
+    let mean_longitude = 218.3164477 + 481267.88123421 * t - 0.0015786 * t * t + t * t * t / 538841.0;
+    let elongation = 297.8501921 + 445267.1114034 * t - 0.0018819 * t * t + t * t * t / 545868.0;
+    let sun_anomaly = 357.5291092 + 35999.0502909 * t - 0.0001536 * t * t;
+    let moon_anomaly = 134.9633964 + 477198.8675055 * t + 0.0087414 * t * t + t * t * t / 69699.0;
+    let node_distance = 93.2720950 + 483202.0175233 * t - 0.0036539 * t * t;
+    let a1 = 119.75 + 131.849 * t;
+    let a2 = 53.09 + 479264.290 * t;
+    let a3 = 313.45 + 481266.484 * t;
+    let eccentricity = 1.0 - 0.002516 * t - 0.0000074 * t * t;
+
+    // terms with the sun's anomaly shrink with the eccentricity of the earth's orbit
+    let argument = |d: i8, m: i8, mp: i8, f: i8| {
+        let angle = f64::from(d) * elongation + f64::from(m) * sun_anomaly + f64::from(mp) * moon_anomaly + f64::from(f) * node_distance;
+        (angle, eccentricity.powi(i32::from(m.abs())))
+    };
+
+    let (mut longitude, mut distance) = (0.0, 0.0);
+    for &(d, m, mp, f, l, r) in MOON_LONGITUDE_TERMS.iter() {
+        let (angle, factor) = argument(d, m, mp, f);
+        longitude += l * factor * dsin(angle);
+        distance += r * factor * dcos(angle);
+    }
+
+    let mut latitude = 0.0;
+    for &(d, m, mp, f, b) in MOON_LATITUDE_TERMS.iter() {
+        let (angle, factor) = argument(d, m, mp, f);
+        latitude += b * factor * dsin(angle);
+    }
+
+    longitude += 3958.0 * dsin(a1) + 1962.0 * dsin(mean_longitude - node_distance) + 318.0 * dsin(a2);
+    latitude += -2235.0 * dsin(mean_longitude) + 382.0 * dsin(a3) + 175.0 * dsin(a1 - node_distance) + 175.0 * dsin(a1 + node_distance)
+        + 127.0 * dsin(mean_longitude - moon_anomaly) - 115.0 * dsin(mean_longitude + moon_anomaly);
+
+    EclipticPosition {
+        longitude: fix_angle(mean_longitude + longitude / 1e6 + nutation(t).0),
+        latitude: latitude / 1e6,
+        distance: 385000.56 + distance / 1000.0,
+    }
+}
+
+// right ascension and declination in degrees of an ecliptic position at a julian day in UT
+pub fn equatorial(position: &EclipticPosition, jd: f64) -> (f64, f64) {
+    let obliquity = nutation(julian_centuries(jd)).1;
+    let (longitude, latitude) = (position.longitude, position.latitude);
+
+    let right_ascension = darctan2(dsin(longitude) * dcos(obliquity) - dtan(latitude) * dsin(obliquity), dcos(longitude));
+    let declination = darcsin(dsin(latitude) * dcos(obliquity) + dcos(latitude) * dsin(obliquity) * dsin(longitude));
+    (fix_angle(right_ascension), declination)
+}
+
+// greenwich mean sidereal time in degrees at a julian day in UT
+pub fn sidereal_time(jd: f64) -> f64 {
+    let t = (jd - 2451545.0) / 36525.0;
+    fix_angle(280.46061837 + 360.98564736629 * (jd - 2451545.0) + 0.000387933 * t * t - t * t * t / 38710000.0)
+}
+
+// azimuth from true north and airless altitude of an ecliptic position seen from the earth's center
+pub fn horizontal(position: &EclipticPosition, jd: f64, latitude: f64, longitude: f64) -> (f64, f64) {
+    let (right_ascension, declination) = equatorial(position, jd);
+    let hour_angle = sidereal_time(jd) + longitude - right_ascension;
+
+    let azimuth = darctan2(dsin(hour_angle), dcos(hour_angle) * dsin(latitude) - dtan(declination) * dcos(latitude)) + 180.0;
+    let altitude = darcsin(dsin(latitude) * dsin(declination) + dcos(latitude) * dcos(declination) * dcos(hour_angle));
+    (fix_angle(azimuth), altitude)
+}
+
+// equatorial horizontal parallax of the moon in degrees
+pub fn moon_parallax(position: &EclipticPosition) -> f64 {
+    darcsin(EARTH_EQUATORIAL_RADIUS / position.distance)
+}
+
+// geocentric altitude of the moon's center at moonrise and moonset, parallax and refraction included
+pub fn moon_horizon_altitude(position: &EclipticPosition) -> f64 {
+    0.7275 * moon_parallax(position) - 0.5667
+}
+
+// moon altitude above its altitude at moonrise and moonset, positive while the moon is up
+pub fn moon_above_horizon(jd: f64, latitude: f64, longitude: f64) -> f64 {
+    let moon = moon_ecliptic(jd);
+    horizontal(&moon, jd, latitude, longitude).1 - moon_horizon_altitude(&moon)
+}
+
+// julian day in UT closest to jd when the moon leads the sun by a phase angle in
+// longitude, 0 for the new moon and 180 for the full moon
+pub fn moon_phase_time(jd: f64, phase: f64) -> f64 {
+    let mut time = jd;
+    for _ in 0..8 {
+        let elongation = moon_ecliptic(time).longitude - sun_ecliptic(time).longitude - phase;
+        let gap = (elongation + 540.0).rem_euclid(360.0) - 180.0;
+        time -= gap / 360.0 * SYNODIC_MONTH;
+    }
+    time
+}
@@ -0,0 +1,75 @@
+// Wall clock of a location from its IANA timezone
+
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+
+// Clock of a location, the machine clock when its timezone is unknown
+#[derive(Clone, Copy)]
+pub enum LocationClock {
+    Zone(Tz),
+    Machine,
+}
+
+impl LocationClock {
+    // clock for an IANA timezone name such as "Asia/Jakarta"
+    pub fn from_timezone(timezone: &str) -> LocationClock {
+        match timezone.trim().trim_matches('"').parse::<Tz>() {
+            Ok(tz) => LocationClock::Zone(tz),
+            Err(_) => LocationClock::Machine,
+        }
+    }
+
+    // utc offset in seconds at an instant
+    fn offset_seconds(&self, instant: DateTime<Utc>) -> i32 {
+        let naive = instant.naive_utc();
+        match self {
+            LocationClock::Zone(tz) => tz.offset_from_utc_datetime(&naive).fix().local_minus_utc(),
+            LocationClock::Machine => Local.offset_from_utc_datetime(&naive).local_minus_utc(),
+        }
+    }
+
+    // date and time of an instant on the location's clock
+    pub fn at(&self, instant: DateTime<Utc>) -> DateTime<FixedOffset> {
+        let offset = FixedOffset::east_opt(self.offset_seconds(instant)).unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+        instant.with_timezone(&offset)
+    }
+
+    // instant of a date and time on the location's clock, the earlier one when the clock
+    // is set back and none when it falls in a skipped hour
+    pub fn instant(&self, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            LocationClock::Zone(tz) => tz.from_local_datetime(&local).earliest().map(|time| time.with_timezone(&Utc)),
+            LocationClock::Machine => Local.from_local_datetime(&local).earliest().map(|time| time.with_timezone(&Utc)),
+        }
+    }
+
+    // current date and time on the location's clock
+    pub fn now(&self) -> DateTime<FixedOffset> {
+        self.at(Utc::now())
+    }
+
+    // current date on the location's clock
+    pub fn today(&self) -> NaiveDate {
+        self.now().date_naive()
+    }
+
+    // utc offset in hours at local noon, the base offset for the prayer times of a date
+    pub fn utc_offset(&self, date: NaiveDate) -> f64 {
+        let noon = Utc.from_utc_datetime(&date.and_hms_opt(12, 0, 0).unwrap_or_default());
+        let guess = self.offset_seconds(noon);
+        let seconds = self.offset_seconds(noon - Duration::seconds(i64::from(guess)));
+        seconds as f64 / 3600.0
+    }
+
+    // hours to add to a time of a date, given on its base offset, when the offset differs
+    // at that moment, e.g. a fajr before the switch to daylight saving time
+    pub fn correction(&self, date: NaiveDate, base_offset: f64, hours: f64) -> f64 {
+        if !hours.is_finite() {
+            return 0.0;
+        }
+
+        let midnight = Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap_or_default());
+        let instant = midnight + Duration::seconds(((hours - base_offset) * 3600.0).round() as i64);
+        self.offset_seconds(instant) as f64 / 3600.0 - base_offset
+    }
+}
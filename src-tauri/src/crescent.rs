@@ -0,0 +1,196 @@
+// Crescent visibility on the evenings after a new moon, after the Yallop and Odeh criteria
+
+use std::cmp::Ordering;
+
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::astronomy::{darccos, dcos, dsin, horizontal, julian_day, moon_above_horizon, moon_ecliptic, moon_parallax, moon_phase_time, sun_ecliptic, SUN_HORIZON_ALTITUDE};
+
+// scan step in days when searching for sunset and moonset
+const SET_SCAN_DAYS: f64 = 10.0 / 1440.0;
+
+// moon semidiameter as a share of its horizontal parallax
+const MOON_SEMIDIAMETER_RATIO: f64 = 0.27245;
+
+// Crescent visibility criterion
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum VisibilityCriterion {
+    // Yallop q value, NAO technical note 69
+    #[default]
+    Yallop,
+    // Odeh V value, from 737 observations
+    Odeh,
+}
+
+impl VisibilityCriterion {
+    pub fn name(&self) -> &'static str {
+        match self {
+            VisibilityCriterion::Yallop => "Yallop",
+            VisibilityCriterion::Odeh => "Odeh",
+        }
+    }
+
+    // criterion value from the arc of vision and crescent width in arc minutes
+    fn value(&self, arc_of_vision: f64, width: f64) -> f64 {
+        let polynomial = -0.1018 * width.powi(3) + 0.7319 * width.powi(2) - 6.3226 * width;
+        match self {
+            VisibilityCriterion::Yallop => (arc_of_vision - (11.8371 + polynomial)) / 10.0,
+            VisibilityCriterion::Odeh => arc_of_vision - (7.1651 + polynomial),
+        }
+    }
+
+    // visibility class and its description for a criterion value
+    pub fn classify(&self, value: f64) -> (&'static str, &'static str) {
+        match self {
+            VisibilityCriterion::Yallop => {
+                if value > 0.216 {
+                    ("A", "Easily visible to the naked eye")
+                } else if value > -0.014 {
+                    ("B", "Visible under perfect conditions")
+                } else if value > -0.160 {
+                    ("C", "May need optical aid to find the crescent")
+                } else if value > -0.232 {
+                    ("D", "Will need optical aid to find the crescent")
+                } else if value > -0.293 {
+                    ("E", "Not visible with a telescope")
+                } else {
+                    ("F", "Not visible, below the Danjon limit")
+                }
+            }
+            VisibilityCriterion::Odeh => {
+                if value >= 5.65 {
+                    ("A", "Visible by naked eye")
+                } else if value >= 2.0 {
+                    ("B", "Visible by optical aid, could be seen by naked eye")
+                } else if value >= -0.96 {
+                    ("C", "Visible by optical aid only")
+                } else {
+                    ("D", "Not visible even by optical aid")
+                }
+            }
+        }
+    }
+}
+
+// Crescent evening structure, times as julian days in UT, angles in degrees
+pub struct CrescentEvening {
+    pub date: NaiveDate,
+    pub conjunction: f64,
+    pub sunset: f64,
+    pub moonset: Option<f64>,
+    pub best_time: f64,
+    // hours since conjunction at the best time
+    pub moon_age: f64,
+    // minutes from sunset to moonset
+    pub lag: Option<f64>,
+    pub arc_of_light: f64,
+    pub arc_of_vision: f64,
+    pub azimuth_difference: f64,
+    pub moon_altitude: f64,
+    // topocentric crescent width in arc minutes
+    pub width: f64,
+    // criterion value, none when the moon sets first or the conjunction follows sunset
+    pub value: Option<f64>,
+}
+
+impl CrescentEvening {
+    // visibility class and its description, or the reason the crescent cannot be seen
+    pub fn class(&self, criterion: VisibilityCriterion) -> (Option<&'static str>, &'static str) {
+        match self.value {
+            Some(value) => {
+                let (class, description) = criterion.classify(value);
+                (Some(class), description)
+            }
+            None if self.conjunction > self.sunset => (None, "Conjunction after sunset"),
+            None => (None, "Moon sets before the sun"),
+        }
+    }
+}
+
+// descending crossings of an altitude between two julian days, refined by bisection
+fn setting_times(start: f64, end: f64, threshold: f64, altitude: &dyn Fn(f64) -> f64) -> Vec<f64> {
+    let mut times = Vec::new();
+    let mut time = start;
+    let mut above = altitude(time) > threshold;
+
+    while time < end {
+        let next = time + SET_SCAN_DAYS;
+        let next_above = altitude(next) > threshold;
+
+        if above && !next_above {
+            let (mut low, mut high) = (time, next);
+            for _ in 0..20 {
+                let middle = (low + high) / 2.0;
+                if altitude(middle) > threshold {
+                    low = middle;
+                } else {
+                    high = middle;
+                }
+            }
+            times.push((low + high) / 2.0);
+        }
+
+        time = next;
+        above = next_above;
+    }
+
+    times
+}
+
+// crescent geometry on the evening of a date, none when the sun does not set
+pub fn crescent_evening(date: NaiveDate, latitude: f64, longitude: f64, criterion: VisibilityCriterion) -> Option<CrescentEvening> {
+    // Search sunset from local solar noon
+    let noon = julian_day(date.year(), date.month(), date.day()) + 0.5 - longitude / 360.0;
+    let sun_altitude = |jd: f64| horizontal(&sun_ecliptic(jd), jd, latitude, longitude).1;
+    let sunset = *setting_times(noon, noon + 0.75, SUN_HORIZON_ALTITUDE, &sun_altitude).first()?;
+
+    // The moonset closest to sunset, before or after it
+    let moon_altitude = |jd: f64| moon_above_horizon(jd, latitude, longitude);
+    let moonset = setting_times(sunset - 0.5, sunset + 0.5, 0.0, &moon_altitude)
+        .into_iter()
+        .min_by(|a, b| (a - sunset).abs().partial_cmp(&(b - sunset).abs()).unwrap_or(Ordering::Equal));
+    let lag = moonset.map(|moonset| (moonset - sunset) * 1440.0);
+
+    // Yallop's best time falls four ninths of the lag after sunset
+    let best_time = sunset + lag.filter(|lag| *lag > 0.0).map_or(0.0, |lag| 4.0 / 9.0 * lag / 1440.0);
+    let conjunction = moon_phase_time(sunset, 0.0);
+
+    let moon = moon_ecliptic(best_time);
+    let (sun_azimuth, sun_altitude) = horizontal(&sun_ecliptic(best_time), best_time, latitude, longitude);
+    let (moon_azimuth, moon_altitude) = horizontal(&moon, best_time, latitude, longitude);
+    let parallax = moon_parallax(&moon);
+    let azimuth_difference = sun_azimuth - moon_azimuth;
+
+    // Topocentric moon altitude and semidiameter seen from the surface
+    let topocentric_altitude = moon_altitude - parallax * dcos(moon_altitude);
+    let semidiameter = MOON_SEMIDIAMETER_RATIO * parallax * 60.0 * (1.0 + dsin(topocentric_altitude) * dsin(parallax));
+    let arc = |moon_altitude: f64| {
+        darccos((dsin(moon_altitude) * dsin(sun_altitude) + dcos(moon_altitude) * dcos(sun_altitude) * dcos(azimuth_difference)).clamp(-1.0, 1.0))
+    };
+
+    // Yallop works from the geocentric arcs, Odeh from the topocentric ones
+    let arc_of_light = arc(moon_altitude);
+    let (arc_of_vision, width) = match criterion {
+        VisibilityCriterion::Yallop => (moon_altitude - sun_altitude, semidiameter * (1.0 - dcos(arc_of_light))),
+        VisibilityCriterion::Odeh => (topocentric_altitude - sun_altitude, semidiameter * (1.0 - dcos(arc(topocentric_altitude)))),
+    };
+
+    let possible = conjunction < sunset && lag.is_some_and(|lag| lag > 0.0);
+    Some(CrescentEvening {
+        date,
+        conjunction,
+        sunset,
+        moonset,
+        best_time,
+        moon_age: (best_time - conjunction) * 24.0,
+        lag,
+        arc_of_light,
+        arc_of_vision,
+        azimuth_difference,
+        moon_altitude: topocentric_altitude,
+        width,
+        value: if possible { Some(criterion.value(arc_of_vision, width)) } else { None },
+    })
+}
@@ -1,8 +1,13 @@
-use rusqlite::{params, Connection, Result};
-use serde::{Deserialize, Serialize};
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use rusqlite::types::Type;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{json, Value};
 use chrono::{NaiveDate, Duration, Datelike};
 
+use crate::hijri::HijriAdjustment;
+use crate::model::CachedLocation;
+use crate::prayer::PrayerSettings;
+
 // PrayerRecord structure
 #[derive(Serialize, Deserialize)]
 pub struct PrayerRecord {
@@ -15,6 +20,33 @@ pub struct PrayerRecord {
     pub isha: bool,
 }
 
+// HijriEvent structure, recurring every year or every month when month is empty
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HijriEvent {
+    pub id: i64,
+    pub name: String,
+    pub month: Option<u32>,
+    pub day: u32,
+    pub notes: Option<String>,
+}
+
+// SavedLocation structure, at most one location is active
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SavedLocation {
+    #[serde(default)]
+    pub id: i64,
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub elevation: Option<f64>,
+    pub city: String,
+    pub region: String,
+    pub country: String,
+    pub timezone: String,
+    #[serde(default)]
+    pub active: bool,
+}
+
 // create table if not exists
 pub fn create_table(conn: &Connection) -> Result<()> {
     conn.execute(
@@ -33,6 +65,246 @@ pub fn create_table(conn: &Connection) -> Result<()> {
     Ok(())
 }
 
+// create settings table if not exists
+pub fn create_settings_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// get setting stored as json
+pub fn get_setting<T: DeserializeOwned>(conn: &Connection, key: &str) -> Result<Option<T>> {
+    let value: Option<String> = conn
+        .query_row("SELECT value FROM settings WHERE key = ?1", params![key], |row| row.get(0))
+        .optional()?;
+
+    match value {
+        Some(text) => serde_json::from_str(&text)
+            .map(Some)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, Type::Text, Box::new(e))),
+        None => Ok(None),
+    }
+}
+
+// save setting as json
+pub fn set_setting<T: Serialize>(conn: &Connection, key: &str, value: &T) -> Result<()> {
+    let text = serde_json::to_string(value).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    conn.execute(
+        "INSERT INTO settings (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, text],
+    )?;
+    Ok(())
+}
+
+// create hijri events table if not exists
+pub fn create_hijri_events_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS hijri_events (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            month INTEGER,
+            day INTEGER NOT NULL,
+            notes TEXT
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// add hijri event and return its id
+pub fn add_hijri_event(conn: &Connection, name: &str, month: Option<u32>, day: u32, notes: Option<&str>) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO hijri_events (name, month, day, notes) VALUES (?1, ?2, ?3, ?4)",
+        params![name, month, day, notes],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+// update hijri event, returns false when the event does not exist
+pub fn update_hijri_event(conn: &Connection, event: &HijriEvent) -> Result<bool> {
+    let updated = conn.execute(
+        "UPDATE hijri_events SET name = ?1, month = ?2, day = ?3, notes = ?4 WHERE id = ?5",
+        params![event.name, event.month, event.day, event.notes, event.id],
+    )?;
+    Ok(updated > 0)
+}
+
+// delete hijri event, returns false when the event does not exist
+pub fn delete_hijri_event(conn: &Connection, id: i64) -> Result<bool> {
+    let deleted = conn.execute("DELETE FROM hijri_events WHERE id = ?1", params![id])?;
+    Ok(deleted > 0)
+}
+
+// get all hijri events
+pub fn get_hijri_events(conn: &Connection) -> Result<Vec<HijriEvent>> {
+    let mut stmt = conn.prepare("SELECT id, name, month, day, notes FROM hijri_events ORDER BY month, day, id")?;
+    let event_iter = stmt.query_map([], |row| {
+        Ok(HijriEvent {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            month: row.get(2)?,
+            day: row.get(3)?,
+            notes: row.get(4)?,
+        })
+    })?;
+
+    let mut events = Vec::new();
+    for event in event_iter {
+        events.push(event?);
+    }
+    Ok(events)
+}
+
+// create saved locations table if not exists
+pub fn create_saved_locations_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS saved_locations (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            latitude REAL NOT NULL,
+            longitude REAL NOT NULL,
+            elevation REAL,
+            city TEXT NOT NULL,
+            region TEXT NOT NULL,
+            country TEXT NOT NULL,
+            timezone TEXT NOT NULL,
+            active BOOLEAN NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// add saved location and return its id
+pub fn add_saved_location(conn: &Connection, location: &SavedLocation) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO saved_locations (name, latitude, longitude, elevation, city, region, country, timezone, active) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 0)",
+        params![
+            location.name,
+            location.latitude,
+            location.longitude,
+            location.elevation,
+            location.city,
+            location.region,
+            location.country,
+            location.timezone,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+// delete saved location, returns false when the location does not exist
+pub fn delete_saved_location(conn: &Connection, id: i64) -> Result<bool> {
+    let deleted = conn.execute("DELETE FROM saved_locations WHERE id = ?1", params![id])?;
+    Ok(deleted > 0)
+}
+
+// make a saved location the only active one, returns false when the location does not exist
+pub fn set_active_saved_location(conn: &Connection, id: i64) -> Result<bool> {
+    let exists: i32 = conn.query_row("SELECT COUNT(*) FROM saved_locations WHERE id = ?1", params![id], |row| row.get(0))?;
+    if exists == 0 {
+        return Ok(false);
+    }
+
+    conn.execute("UPDATE saved_locations SET active = (id = ?1)", params![id])?;
+    Ok(true)
+}
+
+// clear the active saved location
+pub fn clear_active_saved_location(conn: &Connection) -> Result<()> {
+    conn.execute("UPDATE saved_locations SET active = 0", [])?;
+    Ok(())
+}
+
+// map a saved locations row
+fn saved_location_from_row(row: &rusqlite::Row) -> Result<SavedLocation> {
+    Ok(SavedLocation {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        latitude: row.get(2)?,
+        longitude: row.get(3)?,
+        elevation: row.get(4)?,
+        city: row.get(5)?,
+        region: row.get(6)?,
+        country: row.get(7)?,
+        timezone: row.get(8)?,
+        active: row.get(9)?,
+    })
+}
+
+// get all saved locations
+pub fn get_saved_locations(conn: &Connection) -> Result<Vec<SavedLocation>> {
+    let mut stmt = conn.prepare("SELECT id, name, latitude, longitude, elevation, city, region, country, timezone, active FROM saved_locations ORDER BY name, id")?;
+    let location_iter = stmt.query_map([], saved_location_from_row)?;
+
+    let mut locations = Vec::new();
+    for location in location_iter {
+        locations.push(location?);
+    }
+    Ok(locations)
+}
+
+// get the active saved location
+pub fn get_active_saved_location(conn: &Connection) -> Result<Option<SavedLocation>> {
+    conn.query_row(
+        "SELECT id, name, latitude, longitude, elevation, city, region, country, timezone, active FROM saved_locations WHERE active = 1 LIMIT 1",
+        [],
+        saved_location_from_row,
+    )
+    .optional()
+}
+
+// get prayer settings, falling back to the defaults
+pub fn load_prayer_settings(conn: &Connection) -> Result<PrayerSettings> {
+    Ok(get_setting(conn, "prayer_settings")?.unwrap_or_default())
+}
+
+// save prayer settings
+pub fn save_prayer_settings(conn: &Connection, settings: &PrayerSettings) -> Result<()> {
+    set_setting(conn, "prayer_settings", settings)
+}
+
+// get hijri adjustment, no adjustment by default
+pub fn load_hijri_adjustment(conn: &Connection) -> Result<HijriAdjustment> {
+    Ok(get_setting(conn, "hijri_adjustment")?.unwrap_or_default())
+}
+
+// save hijri adjustment
+pub fn save_hijri_adjustment(conn: &Connection, adjustment: &HijriAdjustment) -> Result<()> {
+    set_setting(conn, "hijri_adjustment", adjustment)
+}
+
+// get the last resolved location
+pub fn load_cached_location(conn: &Connection) -> Result<Option<CachedLocation>> {
+    get_setting(conn, "cached_location")
+}
+
+// save the last resolved location
+pub fn save_cached_location(conn: &Connection, cached: &CachedLocation) -> Result<()> {
+    set_setting(conn, "cached_location", cached)
+}
+
+// forget the last resolved location
+pub fn clear_cached_location(conn: &Connection) -> Result<()> {
+    conn.execute("DELETE FROM settings WHERE key = 'cached_location'", [])?;
+    Ok(())
+}
+
+// get location refresh interval in minutes, one hour by default
+pub fn load_location_refresh_minutes(conn: &Connection) -> Result<u32> {
+    Ok(get_setting(conn, "location_refresh_minutes")?.unwrap_or(60))
+}
+
+// save location refresh interval in minutes
+pub fn save_location_refresh_minutes(conn: &Connection, minutes: u32) -> Result<()> {
+    set_setting(conn, "location_refresh_minutes", &minutes)
+}
+
 // add or update prayer record
 pub fn add_or_update_prayer_record(conn: &Connection, record: &PrayerRecord) -> Result<()> {
     let mut stmt = conn.prepare("SELECT COUNT(*) FROM prayer_records WHERE user_id = ?1 AND date = ?2")?;
@@ -116,10 +388,8 @@ pub fn get_monthly_prayer_data(conn: &Connection, user_id: i32, year: i32, month
             ))
         });
 
-        let (fajr, dhuhr, asr, maghrib, isha) = match result {
-            Ok(data) => data,
-            Err(_) => (false, false, false, false, false), // No record for this day
-        };
+        // No record for this day
+        let (fajr, dhuhr, asr, maghrib, isha) = result.unwrap_or_default();
 
         let day_data = json!({
             "date": date,
@@ -162,10 +432,8 @@ pub fn get_prayer_data_in_range(conn: &Connection, user_id: i32, start_date: &st
             ))
         });
 
-        let (fajr, dhuhr, asr, maghrib, isha) = match result {
-            Ok(data) => data,
-            Err(_) => (false, false, false, false, false), // No record for this day
-        };
+        // No record for this day
+        let (fajr, dhuhr, asr, maghrib, isha) = result.unwrap_or_default();
 
         let day_data = json!({
             "date": date,
@@ -0,0 +1,395 @@
+// Solar and lunar eclipses seen from a location, for salat al-kusuf and salat al-khusuf
+
+use std::cmp::Ordering;
+
+use crate::astronomy::{darcsin, darctan2, dcos, dsin, equatorial, horizontal, moon_above_horizon, moon_ecliptic, moon_parallax, moon_phase_time, sidereal_time, sun_ecliptic, EclipticPosition, ASTRONOMICAL_UNIT, EARTH_EQUATORIAL_RADIUS, SUN_HORIZON_ALTITUDE, SYNODIC_MONTH};
+
+// scan step in days when searching for contacts
+const CONTACT_SCAN_DAYS: f64 = 2.0 / 1440.0;
+
+// days on either side of a syzygy searched for an eclipse
+const ECLIPSE_WINDOW_DAYS: f64 = 0.25;
+
+// largest moon latitude in degrees at new and full moon that still allows an eclipse, the lunar
+// one wide enough for the penumbra and the moon at perigee to touch
+const SOLAR_ECLIPSE_LATITUDE: f64 = 1.6;
+const LUNAR_ECLIPSE_LATITUDE: f64 = 1.7;
+
+// moon radius in earth equatorial radii
+const MOON_RADIUS_RATIO: f64 = 0.272481;
+
+// sun semidiameter and horizontal parallax in degrees at one astronomical unit
+const SUN_SEMIDIAMETER: f64 = 959.63 / 3600.0;
+const SUN_PARALLAX: f64 = 8.794 / 3600.0;
+
+// enlargement of the earth's shadow by its atmosphere
+const SHADOW_ENLARGEMENT: f64 = 1.02;
+
+// Eclipse body
+#[derive(Clone, Copy, PartialEq)]
+pub enum EclipseBody {
+    Solar,
+    Lunar,
+}
+
+impl EclipseBody {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EclipseBody::Solar => "solar",
+            EclipseBody::Lunar => "lunar",
+        }
+    }
+
+    // prayer offered during the eclipse
+    pub fn prayer(&self) -> &'static str {
+        match self {
+            EclipseBody::Solar => "Salat al-Kusuf",
+            EclipseBody::Lunar => "Salat al-Khusuf",
+        }
+    }
+}
+
+// Contact structure, time as a julian day in UT and the eclipsed body's altitude in degrees
+pub struct Contact {
+    pub name: &'static str,
+    pub time: f64,
+    pub altitude: f64,
+}
+
+// Eclipse structure, magnitude as the eclipsed share of the body's diameter, by the umbra
+// for lunar eclipses that reach it
+pub struct Eclipse {
+    pub body: EclipseBody,
+    pub kind: &'static str,
+    pub magnitude: f64,
+    pub penumbral_magnitude: Option<f64>,
+    pub maximum: Contact,
+    pub contacts: Vec<Contact>,
+}
+
+impl Eclipse {
+    // prayer offered during the eclipse, none for a penumbral eclipse since the moon is not
+    // visibly darkened by the penumbra alone
+    pub fn prayer(&self) -> Option<&'static str> {
+        if self.kind == "penumbral" {
+            None
+        } else {
+            Some(self.body.prayer())
+        }
+    }
+}
+
+// angular separation in degrees between two equatorial positions
+fn separation(right_ascension: f64, declination: f64, to_right_ascension: f64, to_declination: f64) -> f64 {
+    let haversine = dsin((to_declination - declination) / 2.0).powi(2)
+        + dcos(declination) * dcos(to_declination) * dsin((to_right_ascension - right_ascension) / 2.0).powi(2);
+    2.0 * darcsin(haversine.sqrt().min(1.0))
+}
+
+// topocentric right ascension and declination seen from sea level
+// see Meeus, Astronomical Algorithms, chapter 40
+fn topocentric(position: &EclipticPosition, jd: f64, latitude: f64, longitude: f64) -> (f64, f64) {
+    let (right_ascension, declination) = equatorial(position, jd);
+    let parallax = darcsin(EARTH_EQUATORIAL_RADIUS / position.distance);
+    let reduced_latitude = darctan2(0.99664719 * dsin(latitude), dcos(latitude));
+    let (rho_sin, rho_cos) = (0.99664719 * dsin(reduced_latitude), dcos(reduced_latitude));
+
+    let hour_angle = sidereal_time(jd) + longitude - right_ascension;
+    let denominator = dcos(declination) - rho_cos * dsin(parallax) * dcos(hour_angle);
+    let shift = darctan2(-rho_cos * dsin(parallax) * dsin(hour_angle), denominator);
+    let topocentric_declination = darctan2((dsin(declination) - rho_sin * dsin(parallax)) * dcos(shift), denominator);
+
+    (right_ascension + shift, topocentric_declination)
+}
+
+// Solar disk overlap structure seen from a location, in degrees
+struct SolarDisks {
+    separation: f64,
+    sun_radius: f64,
+    moon_radius: f64,
+    sun_altitude: f64,
+}
+
+fn solar_disks(jd: f64, latitude: f64, longitude: f64) -> SolarDisks {
+    let sun = sun_ecliptic(jd);
+    let moon = moon_ecliptic(jd);
+    let (sun_right_ascension, sun_declination) = topocentric(&sun, jd, latitude, longitude);
+    let (moon_right_ascension, moon_declination) = topocentric(&moon, jd, latitude, longitude);
+    let (_, sun_altitude) = horizontal(&sun, jd, latitude, longitude);
+    let (_, moon_altitude) = horizontal(&moon, jd, latitude, longitude);
+
+    // The moon looks larger the higher it stands
+    let parallax = moon_parallax(&moon);
+    SolarDisks {
+        separation: separation(sun_right_ascension, sun_declination, moon_right_ascension, moon_declination),
+        sun_radius: SUN_SEMIDIAMETER * ASTRONOMICAL_UNIT / sun.distance,
+        moon_radius: darcsin(MOON_RADIUS_RATIO * dsin(parallax)) * (1.0 + dsin(moon_altitude) * dsin(parallax)),
+        sun_altitude,
+    }
+}
+
+// Lunar shadow structure, the moon against the earth's shadow, in degrees
+struct LunarShadow {
+    separation: f64,
+    moon_radius: f64,
+    umbra_radius: f64,
+    penumbra_radius: f64,
+}
+
+fn lunar_shadow(jd: f64) -> LunarShadow {
+    let sun = sun_ecliptic(jd);
+    let moon = moon_ecliptic(jd);
+    let (sun_right_ascension, sun_declination) = equatorial(&sun, jd);
+    let (moon_right_ascension, moon_declination) = equatorial(&moon, jd);
+
+    let parallax = moon_parallax(&moon);
+    let sun_radius = SUN_SEMIDIAMETER * ASTRONOMICAL_UNIT / sun.distance;
+    let sun_parallax = SUN_PARALLAX * ASTRONOMICAL_UNIT / sun.distance;
+    LunarShadow {
+        separation: separation(sun_right_ascension + 180.0, -sun_declination, moon_right_ascension, moon_declination),
+        moon_radius: darcsin(MOON_RADIUS_RATIO * dsin(parallax)),
+        umbra_radius: SHADOW_ENLARGEMENT * (0.99834 * parallax - sun_radius + sun_parallax),
+        penumbra_radius: SHADOW_ENLARGEMENT * (0.99834 * parallax + sun_radius + sun_parallax),
+    }
+}
+
+// times between two julian days when a function turns negative and when it turns positive again
+fn contact_times(start: f64, end: f64, gap: &dyn Fn(f64) -> f64) -> (Option<f64>, Option<f64>) {
+    let refine = |mut low: f64, mut high: f64, low_negative: bool| {
+        for _ in 0..25 {
+            let middle = (low + high) / 2.0;
+            if (gap(middle) < 0.0) == low_negative {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        (low + high) / 2.0
+    };
+
+    let (mut ingress, mut egress) = (None, None);
+    let mut time = start;
+    let mut negative = gap(time) < 0.0;
+    while time < end {
+        let next = time + CONTACT_SCAN_DAYS;
+        let next_negative = gap(next) < 0.0;
+        if !negative && next_negative && ingress.is_none() {
+            ingress = Some(refine(time, next, false));
+        } else if negative && !next_negative {
+            egress = Some(refine(time, next, true));
+        }
+        time = next;
+        negative = next_negative;
+    }
+
+    (ingress, egress)
+}
+
+// julian day of the smallest value of a function between two julian days
+fn minimum_time(start: f64, end: f64, value: &dyn Fn(f64) -> f64) -> f64 {
+    let (mut best, mut best_value) = (start, value(start));
+    let mut time = start;
+    while time <= end {
+        let time_value = value(time);
+        if time_value < best_value {
+            best = time;
+            best_value = time_value;
+        }
+        time += CONTACT_SCAN_DAYS;
+    }
+
+    // Narrow the best sample down by golden section search
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut low, mut high) = (best - CONTACT_SCAN_DAYS, best + CONTACT_SCAN_DAYS);
+    for _ in 0..30 {
+        let left = high - ratio * (high - low);
+        let right = low + ratio * (high - low);
+        if value(left) < value(right) {
+            high = right;
+        } else {
+            low = left;
+        }
+    }
+    (low + high) / 2.0
+}
+
+// whether a condition holds at any scan step between two julian days
+fn any_time(start: f64, end: f64, condition: &dyn Fn(f64) -> bool) -> bool {
+    let mut time = start;
+    while time <= end {
+        if condition(time) {
+            return true;
+        }
+        time += CONTACT_SCAN_DAYS;
+    }
+    condition(end)
+}
+
+// local circumstances of a solar eclipse around a new moon, none when it is not seen
+fn solar_eclipse(new_moon: f64, latitude: f64, longitude: f64) -> Option<Eclipse> {
+    let (start, end) = (new_moon - ECLIPSE_WINDOW_DAYS, new_moon + ECLIPSE_WINDOW_DAYS);
+    let disks = |jd: f64| solar_disks(jd, latitude, longitude);
+    let partial = |jd: f64| {
+        let disks = disks(jd);
+        disks.separation - (disks.sun_radius + disks.moon_radius)
+    };
+    let central = |jd: f64| {
+        let disks = disks(jd);
+        disks.separation - (disks.sun_radius - disks.moon_radius).abs()
+    };
+
+    let (first, last) = contact_times(start, end, &partial);
+    let (first, last) = (first.unwrap_or(start), last.unwrap_or(end));
+    if partial(minimum_time(start, end, &partial)) >= 0.0 {
+        return None;
+    }
+
+    // Seen only when the sun stands above the horizon during the eclipse
+    if !any_time(first, last, &|jd| disks(jd).sun_altitude > SUN_HORIZON_ALTITUDE) {
+        return None;
+    }
+
+    let maximum = minimum_time(first, last, &partial);
+    let peak = disks(maximum);
+    let kind = if peak.separation >= (peak.sun_radius - peak.moon_radius).abs() {
+        "partial"
+    } else if peak.moon_radius > peak.sun_radius {
+        "total"
+    } else {
+        "annular"
+    };
+
+    let contact = |name: &'static str, time: f64| Contact { name, time, altitude: disks(time).sun_altitude };
+    let mut contacts = vec![contact("C1", first)];
+    if kind != "partial" {
+        let (second, third) = contact_times(first, last, &central);
+        contacts.extend(second.map(|time| contact("C2", time)));
+        contacts.extend(third.map(|time| contact("C3", time)));
+    }
+    contacts.push(contact("C4", last));
+
+    Some(Eclipse {
+        body: EclipseBody::Solar,
+        kind,
+        magnitude: (peak.sun_radius + peak.moon_radius - peak.separation) / (2.0 * peak.sun_radius),
+        penumbral_magnitude: None,
+        maximum: contact("Max", maximum),
+        contacts,
+    })
+}
+
+// circumstances of a lunar eclipse around a full moon, none when it is not seen
+fn lunar_eclipse(full_moon: f64, latitude: f64, longitude: f64) -> Option<Eclipse> {
+    let (start, end) = (full_moon - ECLIPSE_WINDOW_DAYS, full_moon + ECLIPSE_WINDOW_DAYS);
+    let penumbral = |jd: f64| {
+        let shadow = lunar_shadow(jd);
+        shadow.separation - (shadow.penumbra_radius + shadow.moon_radius)
+    };
+    let umbral = |jd: f64| {
+        let shadow = lunar_shadow(jd);
+        shadow.separation - (shadow.umbra_radius + shadow.moon_radius)
+    };
+    let total = |jd: f64| {
+        let shadow = lunar_shadow(jd);
+        shadow.separation - (shadow.umbra_radius - shadow.moon_radius)
+    };
+
+    let maximum = minimum_time(start, end, &|jd| lunar_shadow(jd).separation);
+    let peak = lunar_shadow(maximum);
+    let penumbral_magnitude = (peak.penumbra_radius + peak.moon_radius - peak.separation) / (2.0 * peak.moon_radius);
+    if penumbral_magnitude <= 0.0 {
+        return None;
+    }
+
+    // Seen only when the moon stands above the horizon during the eclipse
+    let (first, last) = contact_times(start, end, &penumbral);
+    let (first, last) = (first.unwrap_or(start), last.unwrap_or(end));
+    if !any_time(first, last, &|jd| moon_above_horizon(jd, latitude, longitude) > 0.0) {
+        return None;
+    }
+
+    let magnitude = (peak.umbra_radius + peak.moon_radius - peak.separation) / (2.0 * peak.moon_radius);
+    let kind = if magnitude >= 1.0 {
+        "total"
+    } else if magnitude > 0.0 {
+        "partial"
+    } else {
+        "penumbral"
+    };
+
+    let contact = |name: &'static str, time: f64| Contact { name, time, altitude: horizontal(&moon_ecliptic(time), time, latitude, longitude).1 };
+    let mut contacts = vec![contact("P1", first)];
+    if kind != "penumbral" {
+        let (umbra_first, umbra_last) = contact_times(first, last, &umbral);
+        contacts.extend(umbra_first.map(|time| contact("U1", time)));
+        if kind == "total" {
+            let (second, third) = contact_times(first, last, &total);
+            contacts.extend(second.map(|time| contact("U2", time)));
+            contacts.extend(third.map(|time| contact("U3", time)));
+        }
+        contacts.extend(umbra_last.map(|time| contact("U4", time)));
+    }
+    contacts.push(contact("P4", last));
+
+    Some(Eclipse {
+        body: EclipseBody::Lunar,
+        kind,
+        magnitude: if kind == "penumbral" { penumbral_magnitude } else { magnitude },
+        penumbral_magnitude: Some(penumbral_magnitude),
+        maximum: contact("Max", maximum),
+        contacts,
+    })
+}
+
+// eclipses seen from a location with their maximum between two julian days in UT
+pub fn eclipses(start: f64, end: f64, latitude: f64, longitude: f64) -> Vec<Eclipse> {
+    let mut found = Vec::new();
+
+    // Only syzygies close to the moon's node can make an eclipse
+    for (phase, limit) in [(0.0, SOLAR_ECLIPSE_LATITUDE), (180.0, LUNAR_ECLIPSE_LATITUDE)] {
+        let mut syzygy = moon_phase_time(start - ECLIPSE_WINDOW_DAYS, phase);
+        if syzygy < start - ECLIPSE_WINDOW_DAYS {
+            syzygy = moon_phase_time(syzygy + SYNODIC_MONTH, phase);
+        }
+
+        while syzygy < end + ECLIPSE_WINDOW_DAYS {
+            if moon_ecliptic(syzygy).latitude.abs() < limit {
+                let eclipse = if phase == 0.0 { solar_eclipse(syzygy, latitude, longitude) } else { lunar_eclipse(syzygy, latitude, longitude) };
+                found.extend(eclipse.filter(|eclipse| (start..end).contains(&eclipse.maximum.time)));
+            }
+            syzygy = moon_phase_time(syzygy + SYNODIC_MONTH, phase);
+        }
+    }
+
+    found.sort_by(|a, b| a.maximum.time.partial_cmp(&b.maximum.time).unwrap_or(Ordering::Equal));
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astronomy::julian_day;
+
+    #[test]
+    fn shallow_penumbral_eclipse_is_found_without_a_prayer() {
+        // 18 August 2016, penumbral magnitude 0.017, seen from Honolulu before midnight
+        let start = julian_day(2016, 8, 17);
+        let found = eclipses(start, start + 2.0, 21.3069, -157.8583);
+        assert_eq!(found.len(), 1);
+
+        let eclipse = &found[0];
+        assert!(eclipse.body == EclipseBody::Lunar && eclipse.kind == "penumbral");
+        assert!(eclipse.magnitude > 0.0 && eclipse.magnitude < 0.05, "magnitude {}", eclipse.magnitude);
+        assert!(eclipse.prayer().is_none());
+    }
+
+    #[test]
+    fn total_lunar_eclipse_has_salat_al_khusuf() {
+        // 14 March 2025, umbral magnitude 1.178, seen from New York
+        let start = julian_day(2025, 3, 13);
+        let found = eclipses(start, start + 2.0, 40.7128, -74.006);
+        assert_eq!(found.len(), 1);
+        assert!(found[0].kind == "total");
+        assert_eq!(found[0].prayer(), Some("Salat al-Khusuf"));
+    }
+}
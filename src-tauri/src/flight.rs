@@ -0,0 +1,202 @@
+// Prayer times during a flight along the great circle route between two airports
+
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Duration, Timelike, Utc};
+use serde::Serialize;
+
+use crate::astronomy::{darctan2, dcos, dsin};
+use crate::hijri::HijriAdjustment;
+use crate::prayer::{compute_prayer_times, Coordinates, PrayerSettings};
+use crate::qibla::{central_angle, distance};
+
+// bundled airport list, tab separated
+const AIRPORTS_TSV: &str = include_str!("../data/airports.tsv");
+
+// airports parsed on first use
+static AIRPORTS: OnceLock<Vec<Airport>> = OnceLock::new();
+
+// scan step in minutes when searching the flight for prayer times
+const FLIGHT_SCAN_MINUTES: i64 = 5;
+
+// Airport structure, elevation in meters
+#[derive(Serialize, Clone)]
+pub struct Airport {
+    pub code: String,
+    pub name: String,
+    pub city: String,
+    pub country_code: String,
+    pub country: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub elevation: f64,
+    pub timezone: String,
+}
+
+impl Airport {
+    // parse an airport line, comments and malformed lines are skipped
+    fn parse(line: &str) -> Option<Airport> {
+        if line.starts_with('#') {
+            return None;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 9 {
+            return None;
+        }
+
+        Some(Airport {
+            code: fields[0].to_string(),
+            name: fields[1].to_string(),
+            city: fields[2].to_string(),
+            country_code: fields[3].to_string(),
+            country: fields[4].to_string(),
+            latitude: fields[5].parse().ok()?,
+            longitude: fields[6].parse().ok()?,
+            elevation: fields[7].parse().unwrap_or(0.0),
+            timezone: fields[8].to_string(),
+        })
+    }
+}
+
+// all bundled airports
+pub fn airports() -> &'static [Airport] {
+    AIRPORTS.get_or_init(|| AIRPORTS_TSV.lines().filter_map(Airport::parse).collect())
+}
+
+// airport by its IATA code
+pub fn find_airport(code: &str) -> Option<Airport> {
+    let code = code.trim();
+    airports().iter().find(|airport| airport.code.eq_ignore_ascii_case(code)).cloned()
+}
+
+// search airports by code, city, name or country
+pub fn search_airports(query: &str, limit: usize) -> Vec<Airport> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let mut matches: Vec<(u8, &Airport)> = airports()
+        .iter()
+        .filter_map(|airport| {
+            let rank = if airport.code.to_lowercase() == query {
+                0
+            } else if airport.city.to_lowercase().starts_with(&query) {
+                1
+            } else if [&airport.name, &airport.city, &airport.country].iter().any(|field| field.to_lowercase().contains(&query)) {
+                2
+            } else {
+                return None;
+            };
+            Some((rank, airport))
+        })
+        .collect();
+
+    matches.sort_by_key(|(rank, _)| *rank);
+    matches.into_iter().take(limit).map(|(_, airport)| airport.clone()).collect()
+}
+
+// point at a fraction of the great circle between two points
+pub fn intermediate_point(latitude: f64, longitude: f64, to_latitude: f64, to_longitude: f64, fraction: f64) -> (f64, f64) {
+    let angle = central_angle(latitude, longitude, to_latitude, to_longitude);
+    if angle < 1e-9 {
+        return (latitude, longitude);
+    }
+
+    let a = dsin((1.0 - fraction) * angle) / dsin(angle);
+    let b = dsin(fraction * angle) / dsin(angle);
+    let x = a * dcos(latitude) * dcos(longitude) + b * dcos(to_latitude) * dcos(to_longitude);
+    let y = a * dcos(latitude) * dsin(longitude) + b * dcos(to_latitude) * dsin(to_longitude);
+    let z = a * dsin(latitude) + b * dsin(to_latitude);
+
+    (darctan2(z, (x * x + y * y).sqrt()), darctan2(y, x))
+}
+
+// Flight structure, flown at constant speed along the great circle
+pub struct Flight {
+    pub departure: Airport,
+    pub arrival: Airport,
+    pub departure_time: DateTime<Utc>,
+    pub duration: Duration,
+}
+
+// Flight prayer structure, a prayer time reached during the flight and the position at that moment
+pub struct FlightPrayer {
+    pub prayer: &'static str,
+    pub time: DateTime<Utc>,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Flight {
+    pub fn arrival_time(&self) -> DateTime<Utc> {
+        self.departure_time + self.duration
+    }
+
+    // great circle distance in km
+    pub fn distance(&self) -> f64 {
+        distance(self.departure.latitude, self.departure.longitude, self.arrival.latitude, self.arrival.longitude)
+    }
+
+    // position of the aircraft at an instant of the flight
+    pub fn position(&self, instant: DateTime<Utc>) -> (f64, f64) {
+        let total = self.duration.num_seconds().max(1) as f64;
+        let fraction = ((instant - self.departure_time).num_seconds() as f64 / total).clamp(0.0, 1.0);
+        intermediate_point(self.departure.latitude, self.departure.longitude, self.arrival.latitude, self.arrival.longitude, fraction)
+    }
+
+    // hours from each of the five daily prayers at the aircraft position to an instant, in [-12, 12)
+    fn prayer_offsets(&self, instant: DateTime<Utc>, settings: &PrayerSettings, adjustment: &HijriAdjustment) -> [(&'static str, f64); 5] {
+        let (latitude, longitude) = self.position(instant);
+        let coordinates = Coordinates { latitude, longitude, elevation: None };
+        let times = compute_prayer_times(instant.date_naive(), &coordinates, 0.0, settings, adjustment);
+        let ut = instant.num_seconds_from_midnight() as f64 / 3600.0;
+
+        times.fard_prayers().map(|(prayer, hours)| (prayer, (ut - hours + 12.0).rem_euclid(24.0) - 12.0))
+    }
+
+    // prayer times reached during the flight, in order, from the local times at the moving position
+    pub fn prayer_times(&self, settings: &PrayerSettings, adjustment: &HijriAdjustment) -> Vec<FlightPrayer> {
+        let step = Duration::minutes(FLIGHT_SCAN_MINUTES);
+        let arrival_time = self.arrival_time();
+
+        // scan the flight and refine every prayer time the clock passes by bisection
+        let mut prayers = Vec::new();
+        let mut start = self.departure_time;
+        let mut start_offsets = self.prayer_offsets(start, settings, adjustment);
+        while start < arrival_time {
+            let end = (start + step).min(arrival_time);
+            let end_offsets = self.prayer_offsets(end, settings, adjustment);
+
+            for (index, &(prayer, after)) in end_offsets.iter().enumerate() {
+                let before = start_offsets[index].1;
+
+                // only a prayer time being reached, not the wrap around half a day away
+                let reached = before < 0.0 && after >= 0.0 && after - before < 12.0;
+                if !reached {
+                    continue;
+                }
+
+                let (mut low, mut high) = (start, end);
+                while high - low > Duration::seconds(1) {
+                    let middle = low + Duration::seconds((high - low).num_seconds() / 2);
+                    if self.prayer_offsets(middle, settings, adjustment)[index].1 < 0.0 {
+                        low = middle;
+                    } else {
+                        high = middle;
+                    }
+                }
+
+                let (latitude, longitude) = self.position(high);
+                prayers.push(FlightPrayer { prayer, time: high, latitude, longitude });
+            }
+
+            start = end;
+            start_offsets = end_offsets;
+        }
+
+        prayers.sort_by_key(|prayer| prayer.time);
+        prayers
+    }
+}
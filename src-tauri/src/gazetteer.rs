@@ -0,0 +1,115 @@
+// Offline city search over the bundled gazetteer, a hand-picked list of major cities in
+// the column layout that scripts/gazetteer.mjs builds from the GeoNames cities15000 dump
+
+use std::cmp::Ordering;
+use std::sync::OnceLock;
+
+use serde::Serialize;
+
+use crate::qibla::distance;
+
+// bundled city list, tab separated
+const CITIES_TSV: &str = include_str!("../data/cities.tsv");
+
+// cities parsed on first use
+static CITIES: OnceLock<Vec<City>> = OnceLock::new();
+
+// City structure
+#[derive(Serialize, Clone)]
+pub struct City {
+    pub name: String,
+    pub region: String,
+    pub country_code: String,
+    pub country: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub population: u64,
+    pub timezone: String,
+    #[serde(skip)]
+    lower_name: String,
+    #[serde(skip)]
+    ascii_name: String,
+}
+
+impl City {
+    // parse a gazetteer line, comments and malformed lines are skipped
+    fn parse(line: &str) -> Option<City> {
+        if line.starts_with('#') {
+            return None;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 9 {
+            return None;
+        }
+
+        Some(City {
+            name: fields[0].to_string(),
+            lower_name: fields[0].to_lowercase(),
+            ascii_name: fields[1].to_lowercase(),
+            region: fields[2].to_string(),
+            country_code: fields[3].to_string(),
+            country: fields[4].to_string(),
+            latitude: fields[5].parse().ok()?,
+            longitude: fields[6].parse().ok()?,
+            population: fields[7].parse().unwrap_or(0),
+            timezone: fields[8].to_string(),
+        })
+    }
+
+    // match rank of a lowercase name query, lower is better
+    fn rank(&self, name: &str) -> Option<u8> {
+        let city = &self.lower_name;
+        if city == name || self.ascii_name == name {
+            Some(0)
+        } else if city.starts_with(name) || self.ascii_name.starts_with(name) {
+            Some(1)
+        } else if city.contains(name) || self.ascii_name.contains(name) {
+            Some(2)
+        } else {
+            None
+        }
+    }
+
+    // whether the city lies in the lowercase region or country query
+    fn in_area(&self, area: &str) -> bool {
+        [&self.region, &self.country, &self.country_code]
+            .iter()
+            .any(|field| field.to_lowercase().starts_with(area))
+    }
+}
+
+// all bundled cities
+pub fn cities() -> &'static [City] {
+    CITIES.get_or_init(|| CITIES_TSV.lines().filter_map(City::parse).collect())
+}
+
+// search cities by name, optionally followed by a comma and a region or country
+pub fn search_cities(query: &str, limit: usize) -> Vec<City> {
+    let query = query.trim().to_lowercase();
+    let mut parts = query.splitn(2, ',');
+    let name = parts.next().unwrap_or_default().trim();
+    let area = parts.next().map(str::trim).filter(|area| !area.is_empty());
+    if name.is_empty() {
+        return Vec::new();
+    }
+
+    let mut matches: Vec<(u8, &City)> = cities()
+        .iter()
+        .filter(|city| area.map_or(true, |area| city.in_area(area)))
+        .filter_map(|city| city.rank(name).map(|rank| (rank, city)))
+        .collect();
+
+    // best matches first, larger cities before smaller ones
+    matches.sort_by(|(rank_a, a), (rank_b, b)| rank_a.cmp(rank_b).then(b.population.cmp(&a.population)));
+    matches.into_iter().take(limit).map(|(_, city)| city.clone()).collect()
+}
+
+// bundled city closest to a point, with its distance in km
+pub fn nearest_city(latitude: f64, longitude: f64) -> Option<(City, f64)> {
+    cities()
+        .iter()
+        .map(|city| (city, distance(latitude, longitude, city.latitude, city.longitude)))
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+        .map(|(city, km)| (city.clone(), km))
+}
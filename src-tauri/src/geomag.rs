@@ -0,0 +1,156 @@
+// Magnetic declination from the World Magnetic Model
+// see https://www.ncei.noaa.gov/products/world-magnetic-model
+
+use crate::astronomy::{darctan2, dcos, dsin};
+
+// bundled WMM coefficient file
+const WMM_COF: &str = include_str!("../data/WMM.COF");
+
+// highest degree of the model
+const MAX_DEGREE: usize = 12;
+
+// WGS84 ellipsoid axes and the geomagnetic reference radius in km
+const SEMI_MAJOR_AXIS: f64 = 6378.137;
+const SEMI_MINOR_AXIS: f64 = 6356.7523142;
+const REFERENCE_RADIUS: f64 = 6371.2;
+
+type Coefficients = [[f64; MAX_DEGREE + 1]; MAX_DEGREE + 1];
+
+// Spherical harmonic model with its secular variation
+pub struct MagneticModel {
+    pub name: String,
+    pub epoch: f64,
+    g: Coefficients,
+    h: Coefficients,
+    g_dot: Coefficients,
+    h_dot: Coefficients,
+}
+
+impl MagneticModel {
+    // load the bundled coefficient file
+    pub fn bundled() -> Result<MagneticModel, String> {
+        MagneticModel::parse(WMM_COF)
+    }
+
+    // parse a WMM.COF style coefficient file
+    pub fn parse(text: &str) -> Result<MagneticModel, String> {
+        let mut lines = text.lines();
+        let header: Vec<&str> = lines.next().unwrap_or_default().split_whitespace().collect();
+        let epoch = header
+            .first()
+            .and_then(|epoch| epoch.parse::<f64>().ok())
+            .ok_or("Missing epoch in magnetic model header.")?;
+
+        let mut model = MagneticModel {
+            name: header.get(1).unwrap_or(&"WMM").to_string(),
+            epoch,
+            g: [[0.0; MAX_DEGREE + 1]; MAX_DEGREE + 1],
+            h: [[0.0; MAX_DEGREE + 1]; MAX_DEGREE + 1],
+            g_dot: [[0.0; MAX_DEGREE + 1]; MAX_DEGREE + 1],
+            h_dot: [[0.0; MAX_DEGREE + 1]; MAX_DEGREE + 1],
+        };
+
+        // the file ends with a line of nines
+        for line in lines.take_while(|line| !line.starts_with("9999")) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 6 {
+                continue;
+            }
+
+            let n = fields[0].parse::<usize>().map_err(|e| format!("Invalid magnetic model degree: {}", e))?;
+            let m = fields[1].parse::<usize>().map_err(|e| format!("Invalid magnetic model order: {}", e))?;
+            if n == 0 || n > MAX_DEGREE || m > n {
+                return Err(format!("Magnetic model term {} {} out of range.", n, m));
+            }
+
+            let values = fields[2..6]
+                .iter()
+                .map(|field| field.parse::<f64>())
+                .collect::<Result<Vec<f64>, _>>()
+                .map_err(|e| format!("Invalid magnetic model coefficient: {}", e))?;
+
+            model.g[n][m] = values[0];
+            model.h[n][m] = values[1];
+            model.g_dot[n][m] = values[2];
+            model.h_dot[n][m] = values[3];
+        }
+
+        Ok(model)
+    }
+
+    // declination in degrees, east of true north positive
+    // altitude is in km above the ellipsoid and year is a decimal year
+    pub fn declination(&self, latitude: f64, longitude: f64, altitude: f64, year: f64) -> f64 {
+        let dt = year - self.epoch;
+
+        // geodetic to geocentric spherical coordinates
+        let a2 = SEMI_MAJOR_AXIS * SEMI_MAJOR_AXIS;
+        let b2 = SEMI_MINOR_AXIS * SEMI_MINOR_AXIS;
+        let (slat, clat) = (dsin(latitude), dcos(latitude));
+        let q = (a2 - (a2 - b2) * slat * slat).sqrt();
+        let q1 = altitude * q;
+        let q2 = ((q1 + a2) / (q1 + b2)).powi(2);
+        let ct = slat / (q2 * clat * clat + slat * slat).sqrt();
+        let st = (1.0 - ct * ct).sqrt().max(1e-10);
+        let r = (altitude * altitude + 2.0 * q1 + (a2 * a2 - (a2 * a2 - b2 * b2) * slat * slat) / (q * q)).sqrt();
+        let d = (a2 * clat * clat + b2 * slat * slat).sqrt();
+        let ca = (altitude + d) / r;
+        let sa = (a2 - b2) * clat * slat / (r * d);
+
+        // schmidt semi normalized associated legendre functions and their derivatives
+        let mut p: Coefficients = [[0.0; MAX_DEGREE + 1]; MAX_DEGREE + 1];
+        let mut dp: Coefficients = [[0.0; MAX_DEGREE + 1]; MAX_DEGREE + 1];
+        let mut schmidt: Coefficients = [[0.0; MAX_DEGREE + 1]; MAX_DEGREE + 1];
+        p[0][0] = 1.0;
+        schmidt[0][0] = 1.0;
+
+        for n in 1..=MAX_DEGREE {
+            let nf = n as f64;
+            schmidt[n][0] = schmidt[n - 1][0] * (2.0 * nf - 1.0) / nf;
+
+            for m in 0..=n {
+                let mf = m as f64;
+                if m > 0 {
+                    let factor = if m == 1 { 2.0 } else { 1.0 };
+                    schmidt[n][m] = schmidt[n][m - 1] * ((nf - mf + 1.0) * factor / (nf + mf)).sqrt();
+                }
+
+                if n == m {
+                    p[n][m] = st * p[n - 1][m - 1];
+                    dp[n][m] = st * dp[n - 1][m - 1] + ct * p[n - 1][m - 1];
+                } else if n == 1 {
+                    p[n][m] = ct * p[n - 1][m];
+                    dp[n][m] = ct * dp[n - 1][m] - st * p[n - 1][m];
+                } else {
+                    let k = ((nf - 1.0).powi(2) - mf * mf) / ((2.0 * nf - 1.0) * (2.0 * nf - 3.0));
+                    let (p2, dp2) = if n - 2 >= m { (p[n - 2][m], dp[n - 2][m]) } else { (0.0, 0.0) };
+                    p[n][m] = ct * p[n - 1][m] - k * p2;
+                    dp[n][m] = ct * dp[n - 1][m] - st * p[n - 1][m] - k * dp2;
+                }
+            }
+        }
+
+        // field components along the geocentric radius, colatitude and longitude
+        let (mut br, mut bt, mut bp) = (0.0, 0.0, 0.0);
+        for n in 1..=MAX_DEGREE {
+            let ratio = (REFERENCE_RADIUS / r).powi(n as i32 + 2);
+            for m in 0..=n {
+                let g = self.g[n][m] + dt * self.g_dot[n][m];
+                let h = self.h[n][m] + dt * self.h_dot[n][m];
+                let (sm, cm) = (dsin(m as f64 * longitude), dcos(m as f64 * longitude));
+                let legendre = schmidt[n][m] * p[n][m];
+                let derivative = schmidt[n][m] * dp[n][m];
+
+                br += ratio * (n as f64 + 1.0) * (g * cm + h * sm) * legendre;
+                bt -= ratio * (g * cm + h * sm) * derivative;
+                bp += ratio * m as f64 * (g * sm - h * cm) * legendre / st;
+            }
+        }
+
+        // rotate back to the geodetic north and east components
+        let north = -bt * ca - br * sa;
+        let east = bp;
+
+        darctan2(east, north)
+    }
+}
@@ -85,7 +85,7 @@ pub fn generate_prayer_heatmap_svg(prayer_data: &Value, description: String) ->
     // Draw the legend
     let legend_x = width - 170;
     let legend_y = height - 100;
-    let legend_items = vec![
+    let legend_items = [
         ("#21c35d", "Prayer Done"),
         ("#da204c", "Prayer Not Done"),
     ];
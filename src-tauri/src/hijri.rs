@@ -0,0 +1,480 @@
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+// 1 Muharram 1 AH (16 July 622 Julian) counted in days from 1 January 1 CE
+const ISLAMIC_EPOCH: i32 = 227015;
+
+// First hijri year covered by the published Umm al-Qura table
+const UMM_AL_QURA_FIRST_YEAR: i32 = 1356;
+
+// Month names in the same transliteration as the aladhan api
+const MONTH_NAMES: [&str; 12] = [
+    "Muḥarram",
+    "Ṣafar",
+    "Rabīʿ al-awwal",
+    "Rabīʿ al-thānī",
+    "Jumādá al-ūlá",
+    "Jumādá al-ākhirah",
+    "Rajab",
+    "Shaʿbān",
+    "Ramaḍān",
+    "Shawwāl",
+    "Dhū al-Qaʿdah",
+    "Dhū al-Ḥijjah",
+];
+
+// HijriDate structure
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HijriDate {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+// days from CE of a date in the arithmetic (tabular) islamic calendar
+fn tabular_to_fixed(year: i32, month: u32, day: u32) -> i32 {
+    let month = month as i32;
+    ISLAMIC_EPOCH - 1
+        + (year - 1) * 354
+        + (3 + 11 * year).div_euclid(30)
+        + 29 * (month - 1)
+        + month / 2
+        + day as i32
+}
+
+// arithmetic (tabular) islamic date of a day counted from CE
+fn fixed_to_tabular(fixed: i32) -> HijriDate {
+    let year = (30 * (fixed - ISLAMIC_EPOCH) + 10646).div_euclid(10631);
+    let prior_days = fixed - tabular_to_fixed(year, 1, 1);
+    let month = (11 * prior_days + 330).div_euclid(325) as u32;
+    let day = (fixed - tabular_to_fixed(year, month, 1) + 1) as u32;
+
+    HijriDate { year, month, day }
+}
+
+// new year and month lengths of a year inside the Umm al-Qura table
+fn umm_al_qura_year(year: i32) -> Option<(i32, u16)> {
+    let index = usize::try_from(year - UMM_AL_QURA_FIRST_YEAR).ok()?;
+    UMM_AL_QURA.get(index).copied()
+}
+
+// number of days in a hijri month
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    match umm_al_qura_year(year) {
+        Some((_, lengths)) => 29 + u32::from(lengths >> (month - 1) & 1),
+        None if month == 12 => (tabular_to_fixed(year + 1, 1, 1) - tabular_to_fixed(year, 12, 1)) as u32,
+        None => 30 - (month + 1) % 2,
+    }
+}
+
+// days from CE of the first day of a hijri month
+fn month_start_fixed(year: i32, month: u32) -> i32 {
+    match umm_al_qura_year(year) {
+        Some((new_year, _)) => new_year + (1..month).map(|m| days_in_month(year, m) as i32).sum::<i32>(),
+        None => tabular_to_fixed(year, month, 1),
+    }
+}
+
+// convert a gregorian date to hijri
+pub fn gregorian_to_hijri(date: NaiveDate) -> HijriDate {
+    let fixed = date.num_days_from_ce();
+
+    // outside the published table fall back to the tabular calendar
+    let index = UMM_AL_QURA.partition_point(|(new_year, _)| *new_year <= fixed);
+    let last_year = UMM_AL_QURA_FIRST_YEAR + UMM_AL_QURA.len() as i32 - 1;
+    let table_end = month_start_fixed(last_year, 12) + days_in_month(last_year, 12) as i32;
+    if index == 0 || fixed >= table_end {
+        return fixed_to_tabular(fixed);
+    }
+
+    let year = UMM_AL_QURA_FIRST_YEAR + index as i32 - 1;
+    let mut month = 1;
+    while month < 12 && month_start_fixed(year, month + 1) <= fixed {
+        month += 1;
+    }
+    let day = (fixed - month_start_fixed(year, month) + 1) as u32;
+
+    HijriDate { year, month, day }
+}
+
+// HijriAdjustment structure, days added to the start of hijri months to follow
+// local moon sighting announcements
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct HijriAdjustment {
+    pub days: i32,
+    pub months: Vec<MonthAdjustment>,
+}
+
+// MonthAdjustment structure, overrides the global adjustment for one hijri month
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MonthAdjustment {
+    pub year: i32,
+    pub month: u32,
+    pub days: i32,
+}
+
+// Largest adjustment accepted in either direction
+pub const MAX_ADJUSTMENT_DAYS: i32 = 2;
+
+// the hijri month before and after
+fn previous_month(year: i32, month: u32) -> (i32, u32) {
+    if month == 1 {
+        (year - 1, 12)
+    } else {
+        (year, month - 1)
+    }
+}
+
+fn next_month(year: i32, month: u32) -> (i32, u32) {
+    if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    }
+}
+
+impl HijriAdjustment {
+    // days the start of a month is moved by
+    fn shift(&self, year: i32, month: u32) -> i32 {
+        self.months
+            .iter()
+            .find(|adjustment| adjustment.year == year && adjustment.month == month)
+            .map(|adjustment| adjustment.days)
+            .unwrap_or(self.days)
+    }
+
+    // days from CE of the adjusted first day of a month
+    fn month_start(&self, year: i32, month: u32) -> i32 {
+        month_start_fixed(year, month) + self.shift(year, month)
+    }
+
+    // number of days in a month once its start and the next month's start are moved
+    pub fn days_in_month(&self, year: i32, month: u32) -> u32 {
+        let (next_year, next) = next_month(year, month);
+        (self.month_start(next_year, next) - self.month_start(year, month)) as u32
+    }
+
+    // whether every month around a per-month adjustment still has 29 or 30 days,
+    // moving a single month start changes its own length and the previous one
+    pub fn keeps_month_lengths(&self) -> bool {
+        self.months.iter().all(|adjustment| {
+            let (previous_year, previous) = previous_month(adjustment.year, adjustment.month);
+            [(previous_year, previous), (adjustment.year, adjustment.month)]
+                .iter()
+                .all(|&(year, month)| (29..=30).contains(&(self.days_in_month(year, month) as i32)))
+        })
+    }
+
+    // convert a gregorian date to the locally announced hijri date
+    pub fn to_hijri(&self, date: NaiveDate) -> HijriDate {
+        let fixed = date.num_days_from_ce();
+        let base = gregorian_to_hijri(date);
+
+        // the adjusted month starts at most a couple of days away from the published one
+        let (mut year, mut month) = (base.year, base.month);
+        while self.month_start(year, month) > fixed {
+            (year, month) = previous_month(year, month);
+        }
+        loop {
+            let (next_year, next) = next_month(year, month);
+            if self.month_start(next_year, next) > fixed {
+                break;
+            }
+            (year, month) = (next_year, next);
+        }
+
+        HijriDate { year, month, day: (fixed - self.month_start(year, month) + 1) as u32 }
+    }
+
+    // convert a locally announced hijri date to gregorian
+    pub fn to_gregorian(&self, hijri: &HijriDate) -> Option<NaiveDate> {
+        if !(1..=12).contains(&hijri.month) || hijri.day < 1 || hijri.day > self.days_in_month(hijri.year, hijri.month) {
+            return None;
+        }
+
+        let fixed = self.month_start(hijri.year, hijri.month) + hijri.day as i32 - 1;
+        NaiveDate::from_num_days_from_ce_opt(fixed)
+    }
+}
+
+// Islamic holidays keyed on hijri month and day
+pub const HOLIDAYS: [(u32, u32, &str); 14] = [
+    (1, 1, "Islamic New Year"),
+    (1, 10, "Ashura"),
+    (3, 12, "Mawlid al-Nabi"),
+    (7, 27, "Isra Mi'raj"),
+    (8, 15, "Laylat al-Bara'at"),
+    (9, 1, "Start of Ramadan"),
+    // the odd nights of the last ten days of Ramadan
+    (9, 21, "Laylat al-Qadr"),
+    (9, 23, "Laylat al-Qadr"),
+    (9, 25, "Laylat al-Qadr"),
+    (9, 27, "Laylat al-Qadr"),
+    (9, 29, "Laylat al-Qadr"),
+    (10, 1, "Eid al-Fitr"),
+    (12, 9, "Day of Arafah"),
+    (12, 10, "Eid al-Adha"),
+];
+
+// holidays falling on a hijri day
+pub fn hijri_holidays(hijri: &HijriDate) -> Vec<&'static str> {
+    HOLIDAYS
+        .iter()
+        .filter(|(month, day, _)| *month == hijri.month && *day == hijri.day)
+        .map(|(_, _, name)| *name)
+        .collect()
+}
+
+impl HijriDate {
+    // transliterated month name
+    pub fn month_name(&self) -> &'static str {
+        MONTH_NAMES[(self.month as usize - 1) % 12]
+    }
+
+    // hijri date in the same shape as the aladhan api
+    pub fn to_json(&self) -> Value {
+        json!({
+            "date": format!("{:02}-{:02}-{}", self.day, self.month, self.year),
+            "day": format!("{:02}", self.day),
+            "month": self.month_name(),
+            "year": self.year.to_string(),
+        })
+    }
+}
+
+// Umm al-Qura calendar, 1356 AH to 1500 AH
+// each entry is the first day of the year counted from CE and a mask of the
+// months with 30 days (bit 0 is Muharram)
+const UMM_AL_QURA: [(i32, u16); 145] = [
+    (707182, 0b101101010100), // 1937-03-14
+    (707536, 0b101100100101), // 1938-03-03
+    (707890, 0b101001001011), // 1939-02-20
+    (708244, 0b010100011011), // 1940-02-09
+    (708598, 0b101001011011), // 1941-01-28
+    (708953, 0b001010110110), // 1942-01-18
+    (709307, 0b011010110101), // 1943-01-07
+    (709662, 0b111010101001), // 1943-12-28
+    (710017, 0b111010010010), // 1944-12-17
+    (710371, 0b110100100101), // 1945-12-06
+    (710725, 0b101001001101), // 1946-11-25
+    (711079, 0b010010101101), // 1947-11-14
+    (711433, 0b100101011011), // 1948-11-02
+    (711788, 0b101101011010), // 1949-10-23
+    (712143, 0b011011010010), // 1950-10-13
+    (712497, 0b111010100101), // 1951-10-02
+    (712852, 0b111001001010), // 1952-09-21
+    (713206, 0b110010010110), // 1953-09-10
+    (713560, 0b010100110110), // 1954-08-30
+    (713914, 0b101001110101), // 1955-08-19
+    (714269, 0b010101110100), // 1956-08-08
+    (714623, 0b101101101001), // 1957-07-28
+    (714978, 0b011101010010), // 1958-07-18
+    (715332, 0b011010101001), // 1959-07-07
+    (715686, 0b010101010101), // 1960-06-25
+    (716040, 0b101010101101), // 1961-06-14
+    (716395, 0b010011101100), // 1962-06-04
+    (716749, 0b101011101010), // 1963-05-24
+    (717104, 0b010111010100), // 1964-05-13
+    (717458, 0b110111001001), // 1965-05-02
+    (717813, 0b110101010010), // 1966-04-22
+    (718167, 0b101010100101), // 1967-04-11
+    (718521, 0b010011010101), // 1968-03-30
+    (718875, 0b100101110101), // 1969-03-19
+    (719230, 0b010011110100), // 1970-03-09
+    (719584, 0b101011101001), // 1971-02-26
+    (719939, 0b011011010010), // 1972-02-16
+    (720293, 0b011010100101), // 1973-02-04
+    (720647, 0b010100101011), // 1974-01-24
+    (721001, 0b001001010111), // 1975-01-13
+    (721355, 0b010010110111), // 1976-01-02
+    (721710, 0b100101110110), // 1976-12-22
+    (722065, 0b010101101010), // 1977-12-12
+    (722419, 0b110101100101), // 1978-12-01
+    (722774, 0b110101001010), // 1979-11-21
+    (723128, 0b110010010110), // 1980-11-09
+    (723482, 0b100100101110), // 1981-10-29
+    (723836, 0b001001011101), // 1982-10-18
+    (724190, 0b010011011101), // 1983-10-07
+    (724545, 0b101011010110), // 1984-09-26
+    (724900, 0b011010101010), // 1985-09-16
+    (725254, 0b011010010101), // 1986-09-05
+    (725608, 0b010100100111), // 1987-08-25
+    (725962, 0b101001010111), // 1988-08-13
+    (726317, 0b010010101110), // 1989-08-03
+    (726671, 0b100101101101), // 1990-07-23
+    (727026, 0b001101101010), // 1991-07-13
+    (727380, 0b101101100101), // 1992-07-01
+    (727735, 0b011011001001), // 1993-06-21
+    (728089, 0b011010010011), // 1994-06-10
+    (728443, 0b010100101011), // 1995-05-30
+    (728797, 0b100101100111), // 1996-05-18
+    (729152, 0b001011010110), // 1997-05-08
+    (729506, 0b010111010101), // 1998-04-27
+    (729861, 0b110111010010), // 1999-04-17
+    (730216, 0b101110100100), // 2000-04-06
+    (730570, 0b101101001001), // 2001-03-26
+    (730924, 0b101010010101), // 2002-03-15
+    (731278, 0b010100101101), // 2003-03-04
+    (731632, 0b010110101101), // 2004-02-21
+    (731987, 0b101101101010), // 2005-02-10
+    (732342, 0b011011100100), // 2006-01-31
+    (732696, 0b110111001001), // 2007-01-20
+    (733051, 0b110110010010), // 2008-01-10
+    (733405, 0b101010100110), // 2008-12-29
+    (733759, 0b100101010110), // 2009-12-18
+    (734113, 0b001010101110), // 2010-12-07
+    (734467, 0b010101101101), // 2011-11-26
+    (734822, 0b001101101010), // 2012-11-15
+    (735176, 0b101101010101), // 2013-11-04
+    (735531, 0b101010101010), // 2014-10-25
+    (735885, 0b100101001101), // 2015-10-14
+    (736239, 0b010010011101), // 2016-10-02
+    (736593, 0b100101011101), // 2017-09-21
+    (736948, 0b001010111010), // 2018-09-11
+    (737302, 0b010110110101), // 2019-08-31
+    (737657, 0b010110101010), // 2020-08-20
+    (738011, 0b110101010101), // 2021-08-09
+    (738366, 0b101010011010), // 2022-07-30
+    (738720, 0b100100101110), // 2023-07-19
+    (739074, 0b001010011110), // 2024-07-07
+    (739428, 0b010101011101), // 2025-06-26
+    (739783, 0b101011011010), // 2026-06-16
+    (740138, 0b011011010100), // 2027-06-06
+    (740492, 0b011010100101), // 2028-05-25
+    (740846, 0b110101001011), // 2029-05-14
+    (741201, 0b101010010110), // 2030-05-04
+    (741555, 0b010101001110), // 2031-04-23
+    (741909, 0b101010101110), // 2032-04-11
+    (742264, 0b010110101100), // 2033-04-01
+    (742618, 0b101110101001), // 2034-03-21
+    (742973, 0b110110010010), // 2035-03-11
+    (743327, 0b101100100101), // 2036-02-28
+    (743681, 0b011001001011), // 2037-02-16
+    (744035, 0b110010101011), // 2038-02-05
+    (744390, 0b010101011010), // 2039-01-26
+    (744744, 0b101101010101), // 2040-01-15
+    (745099, 0b011011010010), // 2041-01-04
+    (745453, 0b111010100101), // 2041-12-24
+    (745808, 0b111001001010), // 2042-12-14
+    (746162, 0b101010010101), // 2043-12-03
+    (746516, 0b010100101101), // 2044-11-21
+    (746870, 0b101010101101), // 2045-11-10
+    (747225, 0b001101101100), // 2046-10-31
+    (747579, 0b011101011001), // 2047-10-20
+    (747934, 0b011011010010), // 2048-10-09
+    (748288, 0b011010010101), // 2049-09-28
+    (748642, 0b010100101101), // 2050-09-17
+    (748996, 0b101001011011), // 2051-09-06
+    (749351, 0b010010111010), // 2052-08-26
+    (749705, 0b100110111010), // 2053-08-15
+    (750060, 0b001110110100), // 2054-08-05
+    (750414, 0b101101101001), // 2055-07-25
+    (750769, 0b101101010010), // 2056-07-14
+    (751123, 0b101010100110), // 2057-07-03
+    (751477, 0b010010110110), // 2058-06-22
+    (751831, 0b100101101101), // 2059-06-11
+    (752186, 0b001011101100), // 2060-05-31
+    (752540, 0b011011011001), // 2061-05-20
+    (752895, 0b111010110010), // 2062-05-10
+    (753250, 0b110101010100), // 2063-04-30
+    (753604, 0b110100101010), // 2064-04-18
+    (753958, 0b101001010110), // 2065-04-07
+    (754312, 0b010010110110), // 2066-03-27
+    (754666, 0b100101101101), // 2067-03-16
+    (755021, 0b110101101010), // 2068-03-05
+    (755376, 0b101101010100), // 2069-02-23
+    (755730, 0b101100101001), // 2070-02-12
+    (756084, 0b101010010011), // 2071-02-01
+    (756438, 0b010100101011), // 2072-01-21
+    (756792, 0b101001010111), // 2073-01-09
+    (757147, 0b010100110110), // 2073-12-30
+    (757501, 0b101010110101), // 2074-12-19
+    (757856, 0b011010101010), // 2075-12-09
+    (758210, 0b111010010011), // 2076-11-27
+];
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn assert_hijri(gregorian: NaiveDate, year: i32, month: u32, day: u32) {
+        let hijri = gregorian_to_hijri(gregorian);
+        assert_eq!((hijri.year, hijri.month, hijri.day), (year, month, day), "hijri date of {}", gregorian);
+    }
+
+    #[test]
+    fn known_umm_al_qura_dates() {
+        assert_hijri(date(1937, 3, 14), 1356, 1, 1);
+        assert_hijri(date(2024, 3, 11), 1445, 9, 1);
+        assert_hijri(date(2025, 3, 30), 1446, 10, 1);
+        assert_hijri(date(2025, 6, 6), 1446, 12, 10);
+    }
+
+    #[test]
+    fn known_dates_back_to_gregorian() {
+        let adjustment = HijriAdjustment::default();
+        let gregorian = |year, month, day| adjustment.to_gregorian(&HijriDate { year, month, day });
+
+        assert_eq!(gregorian(1445, 9, 1), Some(date(2024, 3, 11)));
+        assert_eq!(gregorian(1446, 10, 1), Some(date(2025, 3, 30)));
+        assert_eq!(gregorian(1446, 12, 10), Some(date(2025, 6, 6)));
+        assert_eq!(gregorian(1446, 13, 1), None);
+        assert_eq!(gregorian(1446, 9, 31), None);
+    }
+
+    #[test]
+    fn month_adjustments_keep_29_or_30_days() {
+        assert_eq!(days_in_month(1446, 9), 29);
+        assert_eq!(days_in_month(1446, 10), 30);
+        let shawwal = |days| HijriAdjustment { days: 0, months: vec![MonthAdjustment { year: 1446, month: 10, days }] };
+
+        // a day later stretches Ramadan to 30 days and shortens Shawwal to 29
+        assert!(shawwal(1).keeps_month_lengths());
+        assert!(!shawwal(2).keeps_month_lengths());
+        assert!(!shawwal(-1).keeps_month_lengths());
+
+        // a global adjustment moves every month together
+        assert!(HijriAdjustment { days: 2, months: Vec::new() }.keeps_month_lengths());
+        assert!(!HijriAdjustment { days: 2, ..shawwal(0) }.keeps_month_lengths());
+    }
+
+    #[test]
+    fn round_trip_across_the_table_boundaries() {
+        let adjustment = HijriAdjustment::default();
+        let mut previous = gregorian_to_hijri(date(1899, 12, 31));
+        let mut day = date(1900, 1, 1);
+        let mut years = Vec::new();
+
+        // 1900 to 2100 runs from the tabular calendar through 1356 to 1500 AH and back out
+        while day <= date(2100, 12, 31) {
+            let hijri = gregorian_to_hijri(day);
+            assert_eq!(adjustment.to_gregorian(&hijri), Some(day), "round trip of {}", day);
+
+            // every day follows the previous one without gaps or repeats
+            let consecutive = if hijri.day == 1 {
+                previous.day == days_in_month(previous.year, previous.month)
+                    && (hijri.year, hijri.month) == next_month(previous.year, previous.month)
+            } else {
+                (hijri.year, hijri.month, hijri.day) == (previous.year, previous.month, previous.day + 1)
+            };
+            assert!(consecutive, "{} follows {}-{}-{}", day, previous.year, previous.month, previous.day);
+
+            if years.last() != Some(&hijri.year) {
+                years.push(hijri.year);
+            }
+            previous = hijri;
+            day += Duration::days(1);
+        }
+
+        assert!(years.contains(&1355) && years.contains(&1356));
+        assert!(years.contains(&1500) && years.contains(&1501));
+    }
+}
@@ -4,39 +4,79 @@
 mod db;
 mod model;
 mod heatmap;
-
-use db::{PrayerRecord, create_table, add_or_update_prayer_record, get_prayer_records_by_date, get_monthly_prayer_data, get_prayer_data_in_range};
-use model::{Location, TodayVerse, QuranData, Surah, Ayah, AyahTranslation};
+mod astronomy;
+mod prayer;
+mod hijri;
+mod geomag;
+mod qibla;
+mod gazetteer;
+mod clock;
+mod timetable;
+mod flight;
+mod crescent;
+mod eclipse;
+
+use db::{HijriEvent, PrayerRecord, SavedLocation, create_table, create_settings_table, create_hijri_events_table, add_hijri_event, update_hijri_event, delete_hijri_event, get_hijri_events, create_saved_locations_table, add_saved_location, delete_saved_location, set_active_saved_location, clear_active_saved_location, get_saved_locations, get_active_saved_location, get_setting, set_setting, load_cached_location, save_cached_location, clear_cached_location, load_location_refresh_minutes, save_location_refresh_minutes, add_or_update_prayer_record, get_prayer_records_by_date, get_monthly_prayer_data, get_prayer_data_in_range, load_prayer_settings, save_prayer_settings, load_hijri_adjustment, save_hijri_adjustment};
+use model::{CachedLocation, Location, Place, TodayVerse, QuranData, Surah, Ayah, AyahTranslation};
 use heatmap::generate_prayer_heatmap_svg;
+use hijri::{hijri_holidays, HijriAdjustment, HOLIDAYS, HijriDate, MonthAdjustment, MAX_ADJUSTMENT_DAYS};
+use clock::LocationClock;
+use gazetteer::{nearest_city, search_cities as search_gazetteer, City};
+use geomag::MagneticModel;
+use qibla::{distance, kaaba_distance, qibla_bearing, sun_azimuth_times, sun_over_kaaba};
+use astronomy::{julian_day, moon_phase_time, utc_from_julian, SYNODIC_MONTH};
+use crescent::{crescent_evening, VisibilityCriterion};
+use eclipse::{eclipses, Eclipse};
+use flight::{find_airport, search_airports as search_airport_list, Airport, Flight};
+use timetable::{timetable_csv, TimetableCalendar, TimetableRow};
+use prayer::{clock_time, compute_prayer_times, format_countdown, format_time, prayer_diagnostics, AsrSchool, Coordinates, DailySchedule, CalculationMethod, HighLatitudeRule, NearestPrayer, PrayerOffsets, PrayerSettings, RoundingPolicy};
 use rusqlite::Connection;
-use reqwest;
 use serde_json::{Value, json};
-use tauri::{command, State, Window};
-use chrono::{DateTime, FixedOffset, Local, NaiveTime, NaiveDate, Duration};
-use geolocation;
+use tauri::{command, AppHandle, Manager, State, Window};
+use chrono::{DateTime, FixedOffset, Utc, NaiveTime, NaiveDate, NaiveDateTime, Duration, Datelike, Timelike};
+use chrono_tz::Tz;
 use rand::Rng;
 use std::{io::Cursor, sync::{Arc, Mutex}, time::{Duration as StdDuration, Instant}, thread};
 use rodio::{Decoder, OutputStream, Sink, Source};
-use futures::future::join_all;
 
 // App state structure
 struct AppState {
   current_ayah: Mutex<Option<Arc<Sink>>>,
   is_playing: Mutex<bool>,
+  location: Mutex<Option<CachedLocation>>,
+  // unix timestamp and error of the last failed location lookup
+  location_failure: Mutex<Option<(i64, String)>>,
+}
+
+// distance in km a location has to move before listeners are notified
+const LOCATION_CHANGE_KM: f64 = 5.0;
+
+// minutes to wait after a failed location lookup before trying again
+const LOCATION_RETRY_MINUTES: i64 = 5;
+
+// distance in km within which a point is reported over the nearest bundled city and on its timezone
+const NEAREST_CITY_KM: f64 = 500.0;
+
+// get clock of the user location, the machine clock when there is no location
+async fn location_clock(app: AppHandle) -> LocationClock {
+  match get_location(app).await {
+    Ok(location) => LocationClock::from_timezone(&location.timezone),
+    Err(_) => LocationClock::Machine,
+  }
 }
 
 // get local time
 #[command]
-fn get_local_time() -> String {
-  let now = Local::now().to_rfc3339();
+async fn get_local_time(app: AppHandle) -> String {
+  let now = location_clock(app).await.now().to_rfc3339();
   now
 }
 
 // get local date
 #[command]
-fn local_date() -> Result<String, String> {
-  let timestamp = get_local_time();
-  let datetime: DateTime<FixedOffset> = DateTime::parse_from_rfc3339(timestamp.as_str()).map_err(|e| format!("Unexpected error at parsing date: {}", e.to_string()))?;
+async fn local_date(app: AppHandle) -> Result<String, String> {
+  let timestamp = get_local_time(app).await;
+  let datetime: DateTime<FixedOffset> = DateTime::parse_from_rfc3339(timestamp.as_str()).map_err(|e| format!("Unexpected error at parsing date: {}", e))?;
 
   let formatted_date = datetime.format("%d-%m-%Y").to_string();
   Ok(formatted_date)
@@ -44,9 +84,9 @@ fn local_date() -> Result<String, String> {
 
 // get formatted local date
 #[command]
-fn formatted_date() -> Result<String, String> {
-  let timestamp = get_local_time();
-  let datetime: DateTime<FixedOffset> = DateTime::parse_from_rfc3339(timestamp.as_str()).map_err(|e| format!("Unexpected error at formatting date: {}", e.to_string()))?;
+async fn formatted_date(app: AppHandle) -> Result<String, String> {
+  let timestamp = get_local_time(app).await;
+  let datetime: DateTime<FixedOffset> = DateTime::parse_from_rfc3339(timestamp.as_str()).map_err(|e| format!("Unexpected error at formatting date: {}", e))?;
 
   let formatted_date = datetime.format("%-d %B %Y").to_string();
   Ok(formatted_date)
@@ -54,9 +94,9 @@ fn formatted_date() -> Result<String, String> {
 
 // get local clock
 #[command]
-fn local_clock() -> Result<String, String> {
-  let timestamp = get_local_time();
-  let datetime: DateTime<FixedOffset> = DateTime::parse_from_rfc3339(timestamp.as_str()).map_err(|e| format!("Unexpected error at parsing clock: {}", e.to_string()))?;
+async fn local_clock(app: AppHandle) -> Result<String, String> {
+  let timestamp = get_local_time(app).await;
+  let datetime: DateTime<FixedOffset> = DateTime::parse_from_rfc3339(timestamp.as_str()).map_err(|e| format!("Unexpected error at parsing clock: {}", e))?;
 
   let formatted_date = datetime.format("%H:%M:%S").to_string();
   Ok(formatted_date)
@@ -66,17 +106,16 @@ fn local_clock() -> Result<String, String> {
 async fn get_ip() -> Result<String, String> {
   let url = "https://api.ipify.org?format=json";
 
-  let req = reqwest::get(url).await.map_err(|e| format!("Unexpected error at parsing ip: {}", e.to_string()))?;
-  let res: Value = req.json().await.map_err(|e| format!("Unexpected error at ip json parsing: {}", e.to_string()))?;
+  let req = reqwest::get(url).await.map_err(|e| format!("Unexpected error at parsing ip: {}", e))?;
+  let res: Value = req.json().await.map_err(|e| format!("Unexpected error at ip json parsing: {}", e))?;
 
   let ip = res["ip"].as_str().ok_or("IP not found in response".to_string())?.to_string();
 
   Ok(ip)
 }
 
-// get user location
-#[command]
-async fn get_location() -> Result<Location, String> {
+// get user location from the public ip
+async fn ip_location() -> Result<Location, String> {
   let ip = get_ip().await.map_err(|e| format!("Unexpected error at parsing ip: {}", e))?;
 
   let locator = geolocation::find(&ip).map_err(|e| format!("Unexpected error at parsing location: {}", e))?;
@@ -85,6 +124,7 @@ async fn get_location() -> Result<Location, String> {
     ip: locator.ip,
     latitude: locator.latitude,
     longitude: locator.longitude,
+    elevation: None,
     city: locator.city,
     region: locator.region,
     country: locator.country,
@@ -95,6 +135,269 @@ async fn get_location() -> Result<Location, String> {
   Ok(location)
 }
 
+// open database with the saved locations table
+fn open_saved_locations() -> Result<Connection, String> {
+  let conn = open_settings()?;
+  create_saved_locations_table(&conn).map_err(|e| format!("Unexpected error at creating saved locations table: {}", e))?;
+  Ok(conn)
+}
+
+// convert a saved location to the location structure
+fn saved_location_to_location(saved: &SavedLocation) -> Location {
+  Location {
+    ip: String::new(),
+    latitude: saved.latitude.to_string(),
+    longitude: saved.longitude.to_string(),
+    elevation: saved.elevation,
+    city: saved.city.clone(),
+    region: saved.region.clone(),
+    country: saved.country.clone(),
+    timezone: saved.timezone.clone(),
+    location: format!("{},{}", saved.latitude, saved.longitude),
+  }
+}
+
+// resolve user location, the active saved location first and the public ip only when enabled
+async fn resolve_location() -> Result<Location, String> {
+  let conn = open_saved_locations()?;
+  let active = get_active_saved_location(&conn).map_err(|e| format!("Unexpected error at loading active location: {}", e))?;
+  if let Some(saved) = active {
+    return Ok(saved_location_to_location(&saved));
+  }
+
+  if !get_ip_location_enabled()? {
+    return Err("No location selected. Choose a saved location or enable ip based location.".to_string());
+  }
+
+  ip_location().await
+}
+
+// check whether a location came from the ip lookup, saved locations carry no ip
+fn from_ip_lookup(location: &Location) -> bool {
+  !location.ip.is_empty()
+}
+
+// check whether a location moved far enough to notify listeners
+fn location_moved(previous: &Location, current: &Location) -> bool {
+  let coordinates = (location_coordinates(previous), location_coordinates(current));
+  match coordinates {
+    (Ok(previous_coordinates), Ok(current_coordinates)) => {
+      let moved = distance(previous_coordinates.latitude, previous_coordinates.longitude, current_coordinates.latitude, current_coordinates.longitude);
+      moved > LOCATION_CHANGE_KM || previous.timezone != current.timezone
+    },
+    _ => true,
+  }
+}
+
+// resolve the location again, cache it and emit location-changed when it moved
+async fn refresh_cached_location(app: &AppHandle) -> Result<Location, String> {
+  let resolved = resolve_location().await;
+  let state = app.state::<Arc<AppState>>();
+
+  // Remember a failure so that callers polling the location back off instead of retrying
+  let location = match resolved {
+    Ok(location) => location,
+    Err(e) => {
+      *state.location_failure.lock().unwrap() = Some((Utc::now().timestamp(), e.clone()));
+      return Err(e);
+    },
+  };
+  state.location_failure.lock().unwrap().take();
+  let cached = CachedLocation { location: location.clone(), resolved_at: Utc::now().timestamp() };
+
+  // Swap the cached location in memory and in the database
+  let previous = state.location.lock().unwrap().replace(cached.clone());
+  let conn = open_settings()?;
+  save_cached_location(&conn, &cached).map_err(|e| format!("Unexpected error at saving cached location: {}", e))?;
+
+  if previous.map_or(true, |previous| location_moved(&previous.location, &location)) {
+    app.emit_all("location-changed", &location).map_err(|e| format!("Unexpected error at emitting location change: {}", e))?;
+  }
+
+  Ok(location)
+}
+
+// forget the cached location
+fn clear_location_cache(app: &AppHandle) -> Result<(), String> {
+  let state = app.state::<Arc<AppState>>();
+  state.location.lock().unwrap().take();
+  state.location_failure.lock().unwrap().take();
+  let conn = open_settings()?;
+  clear_cached_location(&conn).map_err(|e| format!("Unexpected error at clearing cached location: {}", e))
+}
+
+// get the cached location from memory or the database without looking it up
+fn cached_location(app: &AppHandle) -> Result<Option<CachedLocation>, String> {
+  let cached = app.state::<Arc<AppState>>().location.lock().unwrap().clone();
+  match cached {
+    Some(cached) => Ok(Some(cached)),
+    None => load_cached_location(&open_settings()?).map_err(|e| format!("Unexpected error at loading cached location: {}", e)),
+  }
+}
+
+// get the cached or active saved location without looking it up, however old it is
+fn known_location(app: &AppHandle) -> Result<Option<Location>, String> {
+  if let Some(cached) = cached_location(app)? {
+    return Ok(Some(cached.location));
+  }
+
+  let conn = open_saved_locations()?;
+  let active = get_active_saved_location(&conn).map_err(|e| format!("Unexpected error at loading active location: {}", e))?;
+  Ok(active.map(|saved| saved_location_to_location(&saved)))
+}
+
+// get user location, resolved again only once the cached one is older than the refresh interval
+// and not within the backoff after a failed lookup
+#[command]
+async fn get_location(app: AppHandle) -> Result<Location, String> {
+  let conn = open_settings()?;
+  let refresh_minutes = load_location_refresh_minutes(&conn).map_err(|e| format!("Unexpected error at loading location refresh interval: {}", e))?;
+
+  // Load the cached location from the database on first use
+  let cached = {
+    let state = app.state::<Arc<AppState>>();
+    let mut location = state.location.lock().unwrap();
+    if location.is_none() {
+      *location = load_cached_location(&conn).map_err(|e| format!("Unexpected error at loading cached location: {}", e))?;
+    }
+    location.clone()
+  };
+
+  // Do not look the location up again right after a failure, e.g. while offline
+  let now = Utc::now().timestamp();
+  let failure = app.state::<Arc<AppState>>().location_failure.lock().unwrap().clone();
+  let failure = failure.filter(|(failed_at, _)| now - failed_at < LOCATION_RETRY_MINUTES * 60);
+
+  match (cached, failure) {
+    (Some(cached), _) if now - cached.resolved_at < i64::from(refresh_minutes) * 60 => Ok(cached.location),
+    // Keep using a stale location when it cannot be resolved
+    (Some(cached), Some(_)) => Ok(cached.location),
+    (Some(cached), None) => Ok(refresh_cached_location(&app).await.unwrap_or(cached.location)),
+    (None, Some((_, e))) => Err(e),
+    (None, None) => refresh_cached_location(&app).await,
+  }
+}
+
+// resolve the location now, ignoring the refresh interval
+#[command]
+async fn refresh_location(app: AppHandle) -> Result<Location, String> {
+  refresh_cached_location(&app).await
+}
+
+// get location refresh interval in minutes
+#[command]
+fn get_location_refresh_interval() -> Result<u32, String> {
+  let conn = open_settings()?;
+  load_location_refresh_minutes(&conn).map_err(|e| format!("Unexpected error at loading location refresh interval: {}", e))
+}
+
+// set location refresh interval in minutes
+#[command]
+fn set_location_refresh_interval(minutes: u32) -> Result<(), String> {
+  if minutes == 0 {
+    return Err("Location refresh interval must be at least one minute.".to_string());
+  }
+
+  let conn = open_settings()?;
+  save_location_refresh_minutes(&conn, minutes).map_err(|e| format!("Unexpected error at saving location refresh interval: {}", e))
+}
+
+// get whether the location may be looked up from the public ip
+#[command]
+fn get_ip_location_enabled() -> Result<bool, String> {
+  let conn = open_settings()?;
+  let enabled = get_setting(&conn, "ip_location").map_err(|e| format!("Unexpected error at loading ip location setting: {}", e))?;
+  Ok(enabled.unwrap_or(false))
+}
+
+// opt in or out of the ip based location lookup, the next lookup does not wait for an earlier failure
+// and opting out forgets a location found by the lookup
+#[command]
+fn set_ip_location_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+  let conn = open_settings()?;
+  set_setting(&conn, "ip_location", &enabled).map_err(|e| format!("Unexpected error at saving ip location setting: {}", e))?;
+
+  // A cached saved location is kept
+  if !enabled && cached_location(&app)?.is_some_and(|cached| from_ip_lookup(&cached.location)) {
+    return clear_location_cache(&app);
+  }
+
+  app.state::<Arc<AppState>>().location_failure.lock().unwrap().take();
+  Ok(())
+}
+
+// search the bundled cities, a query like "tripoli, libya" narrows by region or country
+#[command]
+fn search_cities(query: String, limit: Option<usize>) -> Vec<City> {
+  search_gazetteer(&query, limit.unwrap_or(20))
+}
+
+// get saved locations
+#[command]
+fn list_saved_locations() -> Result<Vec<SavedLocation>, String> {
+  let conn = open_saved_locations()?;
+  get_saved_locations(&conn).map_err(|e| format!("Unexpected error at fetching saved locations: {}", e))
+}
+
+// save a location, optionally making it the active one
+#[command]
+async fn save_location(location: SavedLocation, app: AppHandle) -> Result<i64, String> {
+  if location.name.trim().is_empty() {
+    return Err("Location name must not be empty.".to_string());
+  }
+  if !(-90.0..=90.0).contains(&location.latitude) || !(-180.0..=180.0).contains(&location.longitude) {
+    return Err("Coordinates out of range.".to_string());
+  }
+  if location.timezone.parse::<Tz>().is_err() {
+    return Err(format!("Unknown timezone {}.", location.timezone));
+  }
+
+  let conn = open_saved_locations()?;
+  let id = add_saved_location(&conn, &location).map_err(|e| format!("Unexpected error at inserting saved location: {}", e))?;
+  if location.active {
+    set_active_saved_location(&conn, id).map_err(|e| format!("Unexpected error at activating saved location: {}", e))?;
+    refresh_cached_location(&app).await?;
+  }
+  Ok(id)
+}
+
+// remove a saved location
+#[command]
+fn remove_location(id: i64, app: AppHandle) -> Result<(), String> {
+  let conn = open_saved_locations()?;
+  let active = get_active_saved_location(&conn).map_err(|e| format!("Unexpected error at loading active location: {}", e))?;
+  let deleted = delete_saved_location(&conn, id).map_err(|e| format!("Unexpected error at deleting saved location: {}", e))?;
+  if !deleted {
+    return Err(format!("Saved location {} not found.", id));
+  }
+
+  // The next lookup resolves the location again when the active one is gone
+  if active.is_some_and(|active| active.id == id) {
+    clear_location_cache(&app)?;
+  }
+  Ok(())
+}
+
+// pick the active saved location, none falls back to the ip lookup when enabled
+#[command]
+async fn set_active_location(id: Option<i64>, app: AppHandle) -> Result<(), String> {
+  let conn = open_saved_locations()?;
+  match id {
+    Some(id) => {
+      let activated = set_active_saved_location(&conn, id).map_err(|e| format!("Unexpected error at activating saved location: {}", e))?;
+      if !activated {
+        return Err(format!("Saved location {} not found.", id));
+      }
+      refresh_cached_location(&app).await?;
+    },
+    None => {
+      clear_active_saved_location(&conn).map_err(|e| format!("Unexpected error at clearing active location: {}", e))?;
+      clear_location_cache(&app)?;
+    },
+  }
+  Ok(())
+}
+
 // generate random number
 fn generate_random_number() -> u32 {
   let mut rng = rand::thread_rng();
@@ -104,8 +407,8 @@ fn generate_random_number() -> u32 {
 // add user prayer record to database
 #[command]
 async fn add_prayer(user_id: i32, date: String, fajr: bool, dhuhr: bool, asr: bool, maghrib: bool, isha: bool) -> Result<(), String> {
-  let conn = Connection::open("prayer_tracker.db").map_err(|e| format!("Unexpected error at opening database: {}", e.to_string()))?;
-  create_table(&conn).map_err(|e| format!("Unexpected error at creating table: {}", e.to_string()))?;
+  let conn = Connection::open("prayer_tracker.db").map_err(|e| format!("Unexpected error at opening database: {}", e))?;
+  create_table(&conn).map_err(|e| format!("Unexpected error at creating table: {}", e))?;
   let record = PrayerRecord {
     user_id,
     date,
@@ -116,15 +419,15 @@ async fn add_prayer(user_id: i32, date: String, fajr: bool, dhuhr: bool, asr: bo
     isha,
   };
 
-  add_or_update_prayer_record(&conn, &record).map_err(|e| format!("Unexpected error at inserting prayer record: {}", e.to_string()))?;
+  add_or_update_prayer_record(&conn, &record).map_err(|e| format!("Unexpected error at inserting prayer record: {}", e))?;
   Ok(())
 }
 
 // get user prayer record from database
 #[command]
 async fn get_prayer_data_by_date(date: String) -> Result<Vec<PrayerRecord>, String> {
-  let conn = Connection::open("prayer_tracker.db").map_err(|e| format!("Unexpected error at opening database: {}", e.to_string()))?;
-  let records = get_prayer_records_by_date(&conn, &date).map_err(|e| format!("Unexpected error at fetching prayer record: {}", e.to_string()))?;
+  let conn = Connection::open("prayer_tracker.db").map_err(|e| format!("Unexpected error at opening database: {}", e))?;
+  let records = get_prayer_records_by_date(&conn, &date).map_err(|e| format!("Unexpected error at fetching prayer record: {}", e))?;
   Ok(records)
 }
 
@@ -132,20 +435,20 @@ async fn get_prayer_data_by_date(date: String) -> Result<Vec<PrayerRecord>, Stri
 #[command]
 async fn get_prayer_data_by_month(user_id: i32, year: i32, month: u32) -> Result<Value, String> {
   let conn = Connection::open("prayer_tracker.db")
-    .map_err(|e| format!("Unexpected error at opening database: {}", e.to_string()))?;
+    .map_err(|e| format!("Unexpected error at opening database: {}", e))?;
   
   get_monthly_prayer_data(&conn, user_id, year, month)
-    .map_err(|e| format!("Unexpected error at getting monthly prayer data: {}", e.to_string()))
+    .map_err(|e| format!("Unexpected error at getting monthly prayer data: {}", e))
 }
 
 // get user prayer data in range
 #[command]
 async fn get_prayer_data_by_range(user_id: i32, start_date: &str, end_date: &str) -> Result<Value, String> {
   let conn = Connection::open("prayer_tracker.db")
-    .map_err(|e| format!("Unexpected error at opening database: {}", e.to_string()))?;
+    .map_err(|e| format!("Unexpected error at opening database: {}", e))?;
   
   get_prayer_data_in_range(&conn, user_id, start_date, end_date)
-    .map_err(|e| format!("Unexpected error at getting prayer data in range: {}", e.to_string()))
+    .map_err(|e| format!("Unexpected error at getting prayer data in range: {}", e))
 }
 
 // generate heatmap by month
@@ -158,7 +461,7 @@ fn get_prayer_heatmap_by_month(user_id: i32, year: i32, month: u32) -> Result<St
   let description = format!("Prayer Record for {} {}", month_name, year);
 
   let conn = Connection::open("prayer_tracker.db")
-    .map_err(|e| format!("Unexpected error at opening database: {}", e.to_string()))?;
+    .map_err(|e| format!("Unexpected error at opening database: {}", e))?;
 
   match get_monthly_prayer_data(&conn, user_id, year, month) {
     Ok(prayer_data) => {
@@ -173,10 +476,10 @@ fn get_prayer_heatmap_by_month(user_id: i32, year: i32, month: u32) -> Result<St
 #[command]
 fn get_prayer_heatmap_by_range(user_id: i32, start_date: &str, end_date: &str) -> Result<String, String> {
   let start_date_parsed = NaiveDate::parse_from_str(start_date, "%Y-%m-%d")
-    .map_err(|e| format!("Invalid start date format: {}", e.to_string()))?;
+    .map_err(|e| format!("Invalid start date format: {}", e))?;
 
   let end_date_parsed = NaiveDate::parse_from_str(end_date, "%Y-%m-%d")
-    .map_err(|e| format!("Invalid end date format: {}", e.to_string()))?;
+    .map_err(|e| format!("Invalid end date format: {}", e))?;
 
   let start_date_formatted = start_date_parsed.format("%d %b %Y").to_string();
   let end_date_formatted = end_date_parsed.format("%d %b %Y").to_string();
@@ -184,7 +487,7 @@ fn get_prayer_heatmap_by_range(user_id: i32, start_date: &str, end_date: &str) -
   let description = format!("Prayer Record for {} - {}", start_date_formatted, end_date_formatted);
 
   let conn = Connection::open("prayer_tracker.db")
-    .map_err(|e| format!("Unexpected error at opening database: {}", e.to_string()))?;
+    .map_err(|e| format!("Unexpected error at opening database: {}", e))?;
 
   match get_prayer_data_in_range(&conn, user_id, start_date, end_date) {
     Ok(prayer_data) => {
@@ -195,70 +498,94 @@ fn get_prayer_heatmap_by_range(user_id: i32, start_date: &str, end_date: &str) -
   }
 }
 
-// get this month data
+// get hijri adjustment
 #[command]
-async fn get_this_month_data() -> Result<Value, String> {
-  // Get the location (latitude and longitude)
-  let location = get_location().await.map_err(|e| format!("Unexpected error at parsing location for this month data: {}", e.to_string()))?;
-  let lat = location.latitude;
-  let lon = location.longitude;
+fn get_hijri_adjustment() -> Result<HijriAdjustment, String> {
+  let conn = open_settings()?;
+  load_hijri_adjustment(&conn).map_err(|e| format!("Unexpected error at loading hijri adjustment: {}", e))
+}
 
-  // Get the current year and month
-  let timestamp = get_local_time();
-  let datetime: DateTime<FixedOffset> = DateTime::parse_from_rfc3339(timestamp.as_str()).map_err(|e| format!("Unexpected error at parsing date for this month data: {}", e.to_string()))?;
-  let year = datetime.format("%Y").to_string();
-  let month = datetime.format("%m").to_string();
+// set global hijri adjustment
+#[command]
+fn set_hijri_adjustment(days: i32) -> Result<(), String> {
+  if days.abs() > MAX_ADJUSTMENT_DAYS {
+    return Err(format!("Hijri adjustment must be between -{} and {} days.", MAX_ADJUSTMENT_DAYS, MAX_ADJUSTMENT_DAYS));
+  }
 
-  // API URL to fetch the prayer times for the whole month
-  let url = format!("https://api.aladhan.com/v1/calendar/{}/{}?latitude={}&longitude={}", year, month, lat, lon);
-  
-  // Fetch the response from the API
-  let response = reqwest::get(&url).await.map_err(|e| format!("Unexpected error at fetching this month data: {}", e.to_string()))?;
-  let req: Value = response.json().await.map_err(|e| format!("Unexpected error at parsing this month data: {}", e.to_string()))?;
+  let conn = open_settings()?;
+  let mut adjustment = load_hijri_adjustment(&conn).map_err(|e| format!("Unexpected error at loading hijri adjustment: {}", e))?;
+  adjustment.days = days;
+  if !adjustment.keeps_month_lengths() {
+    return Err("Hijri adjustment would leave a month with fewer than 29 or more than 30 days.".to_string());
+  }
 
-  Ok(req)
+  save_hijri_adjustment(&conn, &adjustment).map_err(|e| format!("Unexpected error at saving hijri adjustment: {}", e))
 }
 
-// get hijri calendar
+// set or clear the hijri adjustment of a single month
 #[command]
-async fn get_hijri_calendar() -> Result<Value, String> {
-  // Get the current year and month
-  let timestamp = get_local_time();
-  let datetime: DateTime<FixedOffset> = DateTime::parse_from_rfc3339(timestamp.as_str()).map_err(|e| format!("Unexpected error at parsing date for hijri calendar: {}", e.to_string()))?;
-  let year = datetime.format("%Y").to_string();
-  let month = datetime.format("%m").to_string();
+fn set_hijri_month_adjustment(year: i32, month: u32, days: Option<i32>) -> Result<(), String> {
+  if !(1..=12).contains(&month) {
+    return Err("Hijri month must be between 1 and 12.".to_string());
+  }
+  if days.is_some_and(|days| days.abs() > MAX_ADJUSTMENT_DAYS) {
+    return Err(format!("Hijri adjustment must be between -{} and {} days.", MAX_ADJUSTMENT_DAYS, MAX_ADJUSTMENT_DAYS));
+  }
 
-  // API URL for fetching hijri calendar
-  let url = format!("https://api.aladhan.com/v1/gToHCalendar/{}/{}", month, year);
-  
-  // Fetch the response from the API
-  let response = reqwest::get(url).await.map_err(|e| format!("Unexpected error at fetching hijri calendar: {}", e.to_string()))?;
-  let req: Value = response.json().await.map_err(|e| format!("Unexpected error at parsing hijri calendar: {}", e.to_string()))?;
+  let conn = open_settings()?;
+  let mut adjustment = load_hijri_adjustment(&conn).map_err(|e| format!("Unexpected error at loading hijri adjustment: {}", e))?;
 
-  // Check if the response contains data
-  let data = req["data"].as_array().ok_or("No data found in API response for hijri calendar.")?;
+  // Replace any previous adjustment for the month
+  adjustment.months.retain(|m| !(m.year == year && m.month == month));
+  if let Some(days) = days {
+    adjustment.months.push(MonthAdjustment { year, month, days });
+  }
+  if !adjustment.keeps_month_lengths() {
+    return Err("Hijri adjustment would leave a month with fewer than 29 or more than 30 days.".to_string());
+  }
+
+  save_hijri_adjustment(&conn, &adjustment).map_err(|e| format!("Unexpected error at saving hijri adjustment: {}", e))
+}
+
+// get hijri dates of every day in a gregorian month
+fn hijri_month_days(year: i32, month: u32, adjustment: &HijriAdjustment) -> Result<Vec<(NaiveDate, HijriDate)>, String> {
+  let mut date = NaiveDate::from_ymd_opt(year, month, 1).ok_or("Invalid month at hijri calendar.")?;
+  let mut days = Vec::new();
+
+  while date.month() == month {
+    days.push((date, adjustment.to_hijri(date)));
+    date += Duration::days(1);
+  }
+
+  Ok(days)
+}
+
+// get hijri calendar
+#[command]
+async fn get_hijri_calendar(app: AppHandle) -> Result<Value, String> {
+  // Get the current year and month
+  let today = location_clock(app).await.today();
+  let adjustment = get_hijri_adjustment()?;
 
   // Process the data
   let mut processed_data = Vec::new();
 
-  // Iterate over the data and process each entry
-  for entry in data {
-    if let Some(hijri) = entry["hijri"].as_object() {
-      let mut processed_entry = json!({
-        "day": hijri.get("day").cloned().unwrap_or(Value::Null),
-        "month": hijri.get("month").and_then(|m| m.get("en")).cloned().unwrap_or(Value::Null),
-        "year": hijri.get("year").cloned().unwrap_or(Value::Null),
-      });
-
-      // Check if the entry contains any holidays
-      if let Some(holidays) = hijri.get("holidays").and_then(|h| h.as_array()) {
-        if !holidays.is_empty() {
-          processed_entry["holiday"] = json!(holidays[0]);
-        }
-      }
+  // Iterate over the days of the month and convert each one
+  for (_, hijri) in hijri_month_days(today.year(), today.month(), &adjustment)? {
+    let hijri_json = hijri.to_json();
+    let mut processed_entry = json!({
+      "day": hijri_json["day"],
+      "month": hijri_json["month"],
+      "year": hijri_json["year"],
+    });
 
-      processed_data.push(processed_entry);
+    // Check if the day has any holidays
+    let holidays = hijri_holidays(&hijri);
+    if !holidays.is_empty() {
+      processed_entry["holiday"] = json!(holidays[0]);
     }
+
+    processed_data.push(processed_entry);
   }
 
   // Return the processed data
@@ -268,95 +595,280 @@ async fn get_hijri_calendar() -> Result<Value, String> {
 // get hijri calendar
 #[command]
 async fn get_hijri_calendar_by_month(month: String, year: String) -> Result<Value, String> {
-  // API URL for fetching hijri calendar
-  let url = format!("https://api.aladhan.com/v1/gToHCalendar/{}/{}", month, year);
-  
-  // Fetch the response from the API
-  let response = reqwest::get(&url).await.map_err(|e| format!("Unexpected error at fetching hijri calendar by month: {}", e.to_string()))?;
-  let req: Value = response.json().await.map_err(|e| format!("Unexpected error at parsing hijri calendar by month: {}", e.to_string()))?;
+  let month = month.parse::<u32>().map_err(|e| format!("Unexpected error at parsing month for hijri calendar by month: {}", e))?;
+  let year = year.parse::<i32>().map_err(|e| format!("Unexpected error at parsing year for hijri calendar by month: {}", e))?;
+  let adjustment = get_hijri_adjustment()?;
 
-  // Check if the response contains data
-  let data = req["data"].as_array().ok_or("No data found in API response for hijri calendar by month.")?;
+  Ok(Value::Array(hijri_calendar_days(year, month, &adjustment)?))
+}
 
+// hijri dates and holidays of every day of a gregorian month
+fn hijri_calendar_days(year: i32, month: u32, adjustment: &HijriAdjustment) -> Result<Vec<Value>, String> {
   // Process the data
   let mut processed_data = Vec::new();
-  let mut futures = Vec::new();
-
-  // Iterate over the data and process each entry
-  for entry in data {
-    if let Some(hijri) = entry["hijri"].as_object() {
-      let mut processed_entry = json!({
-        "date": hijri.get("date").cloned().unwrap_or(Value::Null),
-        "day": hijri.get("day").cloned().unwrap_or(Value::Null),
-        "month": hijri.get("month").and_then(|m| m.get("en")).cloned().unwrap_or(Value::Null),
-        "year": hijri.get("year").cloned().unwrap_or(Value::Null),
-      });
 
-      // Check if the entry contains any holidays
-      if let Some(holidays) = hijri.get("holidays").and_then(|h| h.as_array()) {
-        if !holidays.is_empty() {
-          processed_entry["holidays"] = json!(holidays);
-        }
-      }
+  // Iterate over the days of the month and convert each one
+  for (date, hijri) in hijri_month_days(year, month, adjustment)? {
+    let mut processed_entry = hijri.to_json();
 
-      // Convert Hijri date to Gregorian date
-      if let Some(hijri_date) = hijri.get("date").and_then(|d| d.as_str()) {
-        let url = format!("https://api.aladhan.com/v1/hToG/{}", hijri_date);
-        futures.push(async move {
-          let response = reqwest::get(&url).await.map_err(|e| e.to_string())?;
-          let req: Value = response.json().await.map_err(|e| format!("Unexpected error at parsing Hijri date to Gregorian date: {}", e.to_string()))?;
-          if let Some(gregorian_date) = req["data"]["gregorian"]["date"].as_str() {
-            processed_entry["gregorian_date"] = json!(gregorian_date);
-          }
-          Ok(processed_entry)
-        });
-      } else {
-        processed_data.push(processed_entry);
-      }
+    // Check if the day has any holidays
+    let holidays = hijri_holidays(&hijri);
+    if !holidays.is_empty() {
+      processed_entry["holidays"] = json!(holidays);
     }
+
+    processed_entry["gregorian_date"] = json!(date.format("%d-%m-%Y").to_string());
+    processed_data.push(processed_entry);
   }
 
-  // Await all futures and collect results
-  let results: Vec<Result<Value, String>> = join_all(futures).await;
-  for result in results {
-    if let Ok(entry) = result {
-      processed_data.push(entry);
+  Ok(processed_data)
+}
+
+// local date and time of a julian day on a location's clock
+fn format_julian(jd: f64, clock: LocationClock) -> Option<String> {
+  utc_from_julian(jd).map(|instant| clock.at(instant).format("%Y-%m-%d %H:%M").to_string())
+}
+
+// get the hijri calendar of a gregorian month with the crescent visibility on the evenings
+// after each conjunction, at the detected location unless coordinates are given
+#[command]
+async fn get_crescent_visibility(app: AppHandle, month: String, year: String, criterion: Option<VisibilityCriterion>, latitude: Option<f64>, longitude: Option<f64>, timezone: Option<String>) -> Result<Value, String> {
+  let month = month.parse::<u32>().map_err(|e| format!("Unexpected error at parsing month for crescent visibility: {}", e))?;
+  let year = year.parse::<i32>().map_err(|e| format!("Unexpected error at parsing year for crescent visibility: {}", e))?;
+  let first = NaiveDate::from_ymd_opt(year, month, 1).ok_or("Invalid month at crescent visibility.")?;
+  let criterion = criterion.unwrap_or_default();
+  let adjustment = get_hijri_adjustment()?;
+
+  let (coordinates, clock) = resolve_coordinates(app, latitude, longitude, None, timezone).await?;
+  let mut days = hijri_calendar_days(year, month, &adjustment)?;
+
+  // Every conjunction from a few days before the month to its last day
+  let start = julian_day(year, month, 1) - 3.0;
+  let end = julian_day(year, month, 1) + days.len() as f64;
+  let mut conjunction = moon_phase_time(start, 0.0);
+  if conjunction < start {
+    conjunction = moon_phase_time(conjunction + SYNODIC_MONTH, 0.0);
+  }
+
+  let mut crescents = Vec::new();
+  while conjunction < end {
+    // The evening of the conjunction and the two that follow
+    let conjunction_date = utc_from_julian(conjunction).map_or(first, |instant| clock.at(instant).date_naive());
+    let mut evenings = Vec::new();
+    for date in (0..3).map(|days| conjunction_date + Duration::days(days)) {
+      let evening = match crescent_evening(date, coordinates.latitude, coordinates.longitude, criterion) {
+        Some(evening) => evening,
+        None => continue,
+      };
+      let (class, description) = evening.class(criterion);
+      let entry = json!({
+        "date": evening.date.format("%Y-%m-%d").to_string(),
+        "hijri": adjustment.to_hijri(evening.date).to_json(),
+        "sunset": format_julian(evening.sunset, clock),
+        "moonset": evening.moonset.and_then(|moonset| format_julian(moonset, clock)),
+        "best_time": format_julian(evening.best_time, clock),
+        "moon_age_hours": evening.moon_age,
+        "lag_minutes": evening.lag,
+        "elongation": evening.arc_of_light,
+        "arc_of_vision": evening.arc_of_vision,
+        "azimuth_difference": evening.azimuth_difference,
+        "moon_altitude": evening.moon_altitude,
+        "width": evening.width,
+        "value": evening.value,
+        "class": class,
+        "description": description,
+      });
+
+      // Show the evening on its day of the calendar
+      let gregorian_date = evening.date.format("%d-%m-%Y").to_string();
+      if let Some(day) = days.iter_mut().find(|day| day["gregorian_date"] == json!(gregorian_date)) {
+        day["crescent"] = entry.clone();
+      }
+      evenings.push(entry);
     }
+
+    crescents.push(json!({
+      "conjunction": format_julian(conjunction, clock),
+      "evenings": evenings,
+    }));
+    conjunction = moon_phase_time(conjunction + SYNODIC_MONTH, 0.0);
   }
 
-  // Return the processed data
-  Ok(Value::Array(processed_data))
+  Ok(json!({
+    "criterion": criterion.name(),
+    "days": days,
+    "crescents": crescents,
+  }))
+}
+
+// open database with the hijri events table
+fn open_hijri_events() -> Result<Connection, String> {
+  let conn = Connection::open("prayer_tracker.db").map_err(|e| format!("Unexpected error at opening database: {}", e))?;
+  create_hijri_events_table(&conn).map_err(|e| format!("Unexpected error at creating hijri events table: {}", e))?;
+  Ok(conn)
+}
+
+// check the hijri date of a user event
+fn validate_hijri_event(name: &str, month: Option<u32>, day: u32) -> Result<(), String> {
+  if name.trim().is_empty() {
+    return Err("Event name must not be empty.".to_string());
+  }
+  if month.is_some_and(|month| !(1..=12).contains(&month)) {
+    return Err("Hijri month must be between 1 and 12.".to_string());
+  }
+  if !(1..=30).contains(&day) {
+    return Err("Hijri day must be between 1 and 30.".to_string());
+  }
+  Ok(())
+}
+
+// get user defined hijri events
+#[command]
+fn get_user_events() -> Result<Vec<HijriEvent>, String> {
+  let conn = open_hijri_events()?;
+  get_hijri_events(&conn).map_err(|e| format!("Unexpected error at fetching hijri events: {}", e))
 }
 
-// check holidays
+// add user defined hijri event, an empty month repeats it every month
 #[command]
-async fn check_holidays(month: String, year: String) -> Result<Value, String> {
-  // Call the existing function to get the Hijri calendar data
-  let calendar_data = get_hijri_calendar_by_month(month, year).await?;
+fn add_user_event(name: String, month: Option<u32>, day: u32, notes: Option<String>) -> Result<i64, String> {
+  validate_hijri_event(&name, month, day)?;
+  let conn = open_hijri_events()?;
+  add_hijri_event(&conn, name.trim(), month, day, notes.as_deref())
+    .map_err(|e| format!("Unexpected error at inserting hijri event: {}", e))
+}
 
-  // Check if the calendar data is an array
-  let data_array = calendar_data.as_array().ok_or("Unexpected data format at checking holidays.")?;
+// update user defined hijri event
+#[command]
+fn update_user_event(event: HijriEvent) -> Result<(), String> {
+  validate_hijri_event(&event.name, event.month, event.day)?;
+  let conn = open_hijri_events()?;
+  let updated = update_hijri_event(&conn, &event).map_err(|e| format!("Unexpected error at updating hijri event: {}", e))?;
+  if !updated {
+    return Err(format!("Hijri event {} not found.", event.id));
+  }
+  Ok(())
+}
 
+// delete user defined hijri event
+#[command]
+fn delete_user_event(id: i64) -> Result<(), String> {
+  let conn = open_hijri_events()?;
+  let deleted = delete_hijri_event(&conn, id).map_err(|e| format!("Unexpected error at deleting hijri event: {}", e))?;
+  if !deleted {
+    return Err(format!("Hijri event {} not found.", id));
+  }
+  Ok(())
+}
+
+// user events falling on a hijri day, events on the 30th move to the 29th in short months
+fn user_events_on(events: &[HijriEvent], hijri: &HijriDate, month_length: u32) -> Vec<HijriEvent> {
+  events
+    .iter()
+    .filter(|event| event.month.map_or(true, |month| month == hijri.month))
+    .filter(|event| event.day == hijri.day || (event.day > month_length && hijri.day == month_length))
+    .cloned()
+    .collect()
+}
+
+// day with holidays, user events or eclipses in a gregorian month
+struct HolidayDay {
+  date: NaiveDate,
+  hijri: HijriDate,
+  holidays: Vec<&'static str>,
+  events: Vec<HijriEvent>,
+  eclipses: Vec<Value>,
+}
+
+// eclipse with its contact times on a location's clock
+fn eclipse_json(eclipse: &Eclipse, clock: LocationClock) -> Value {
+  let contact = |name: &str, time: f64, altitude: f64| json!({
+    "name": name,
+    "time": format_julian(time, clock),
+    "altitude": altitude,
+  });
+
+  json!({
+    "body": eclipse.body.name(),
+    "prayer": eclipse.prayer(),
+    "type": eclipse.kind,
+    "magnitude": eclipse.magnitude,
+    "penumbral_magnitude": eclipse.penumbral_magnitude,
+    "maximum": contact(eclipse.maximum.name, eclipse.maximum.time, eclipse.maximum.altitude),
+    "contacts": eclipse.contacts.iter().map(|c| contact(c.name, c.time, c.altitude)).collect::<Vec<Value>>(),
+  })
+}
+
+// local dates of maximum and details of the eclipses seen between two dates
+fn eclipses_between(start: NaiveDate, end: NaiveDate, coordinates: &Coordinates, clock: LocationClock) -> Vec<(NaiveDate, Value)> {
+  // Search a day beyond each end and keep the maxima on the location's dates
+  let start_jd = julian_day(start.year(), start.month(), start.day()) - 1.0;
+  let end_jd = julian_day(end.year(), end.month(), end.day()) + 2.0;
+
+  eclipses(start_jd, end_jd, coordinates.latitude, coordinates.longitude)
+    .iter()
+    .filter_map(|eclipse| {
+      let date = clock.at(utc_from_julian(eclipse.maximum.time)?).date_naive();
+      (start <= date && date <= end).then(|| (date, eclipse_json(eclipse, clock)))
+    })
+    .collect()
+}
+
+// get days with holidays in a gregorian month, with the eclipses seen from the user location when known
+fn month_holidays(app: &AppHandle, month: &str, year: &str) -> Result<Vec<HolidayDay>, String> {
+  let month = month.parse::<u32>().map_err(|e| format!("Unexpected error at parsing month for holidays: {}", e))?;
+  let year = year.parse::<i32>().map_err(|e| format!("Unexpected error at parsing year for holidays: {}", e))?;
+  let adjustment = get_hijri_adjustment()?;
+  let events = get_user_events()?;
+  let month_days = hijri_month_days(year, month, &adjustment)?;
+
+  // Eclipses only at a location already known, listing holidays never looks one up
+  let location = known_location(app).ok().flatten();
+  let coordinates = location.as_ref().and_then(|location| location_coordinates(location).ok());
+  let mut eclipses = match (location, coordinates, month_days.first(), month_days.last()) {
+    (Some(location), Some(coordinates), Some((first, _)), Some((last, _))) => {
+      eclipses_between(*first, *last, &coordinates, LocationClock::from_timezone(&location.timezone))
+    },
+    _ => Vec::new(),
+  };
+
+  // Only eclipses with a prayer stand next to the holidays, not penumbral ones
+  eclipses.retain(|(_, eclipse)| !eclipse["prayer"].is_null());
+
+  // Keep only the days found in the holiday table, the user events or the eclipses
+  let days = month_days
+    .into_iter()
+    .map(|(date, hijri)| {
+      let month_length = adjustment.days_in_month(hijri.year, hijri.month);
+      HolidayDay {
+        date,
+        holidays: hijri_holidays(&hijri),
+        events: user_events_on(&events, &hijri, month_length),
+        eclipses: eclipses.iter().filter(|(day, _)| *day == date).map(|(_, eclipse)| eclipse.clone()).collect(),
+        hijri,
+      }
+    })
+    .filter(|day| !day.holidays.is_empty() || !day.events.is_empty() || !day.eclipses.is_empty())
+    .collect();
+
+  Ok(days)
+}
+
+// check holidays, each day lists its holidays, user events and eclipses and a day
+// may have only user events or eclipses, so consumers read all three arrays
+#[command]
+async fn check_holidays(app: AppHandle, month: String, year: String) -> Result<Value, String> {
   // Initialize an array to store days with holidays
   let mut holidays = Vec::new();
 
-  // Iterate over the data and check for holidays
-  for entry in data_array {
-    if let Some(day) = entry.get("day") {
-      if let Some(holidays_list) = entry.get("holidays").and_then(|h| h.as_array()) {
-        if !holidays_list.is_empty() {
-          // If there are holidays, add the day and holidays to the array
-          holidays.push(json!({
-            "date": entry.get("date").cloned().unwrap_or(Value::Null),
-            "gregorian_date": entry.get("gregorian_date").cloned().unwrap_or(Value::Null),
-            "day": day,
-            "month": entry.get("month").and_then(|m| m.as_str()),
-            "year": entry.get("year").and_then(|y| y.as_str()),
-            "holidays": holidays_list
-          }));
-        }
-      }
-    }
+  // Add the day, holidays and user events to the array
+  for day in month_holidays(&app, &month, &year)? {
+    let mut entry = day.hijri.to_json();
+    entry["gregorian_date"] = json!(day.date.format("%d-%m-%Y").to_string());
+    entry["holidays"] = json!(day.holidays);
+    entry["user_events"] = json!(day.events);
+    entry["eclipses"] = json!(day.eclipses);
+    holidays.push(entry);
   }
 
   // Return the array of days with holidays
@@ -365,255 +877,840 @@ async fn check_holidays(month: String, year: String) -> Result<Value, String> {
 
 // get holiday days array
 #[command]
-async fn get_holiday_days(month: String, year: String) -> Result<Vec<u32>, String> {
-  // Call the existing function to get the holidays
-  let holidays_data = check_holidays(month, year).await?;
+async fn get_holiday_days(app: AppHandle, month: String, year: String) -> Result<Vec<u32>, String> {
+  // Return the array of Gregorian days with holidays, user events or eclipses
+  let holiday_days = month_holidays(&app, &month, &year)?
+    .iter()
+    .map(|day| day.date.day())
+    .collect();
 
-  // Check if the holidays data is an array
-  let data_array = holidays_data.as_array().ok_or("Unexpected data format at getting holiday days.")?;
+  Ok(holiday_days)
+}
 
-  // Initialize an array to store days with holidays
-  let mut holiday_days = Vec::new();
-
-  // Iterate over the data and extract the days
-  for entry in data_array {
-    // Construct the Hijri date string
-    if let Some(date) = entry.get("date").and_then(|d| d.as_str()) {
-      // Call the API to convert Hijri date to Gregorian date
-      let url = format!("https://api.aladhan.com/v1/hToG/{}", date);
-      let response = reqwest::get(&url).await.map_err(|e| format!("Unexpected error at converting Hijri date to Gregorian date for holidays: {}", e.to_string()))?;
-      let req: Value = response.json().await.map_err(|e| format!("Unexpected error at parsing Hijri date to Gregorian date for holidays: {}", e.to_string()))?;
-
-      // Extract the Gregorian date from the response
-      if let Some(gregorian_date) = req["data"]["gregorian"]["day"].as_str() {
-        // Parse the Gregorian day as a u32 and add it to the array
-        if let Ok(day_num) = gregorian_date.parse::<u32>() {
-          holiday_days.push(day_num);
-        }
-      }
+// get holidays of a hijri year
+#[command]
+fn get_holidays_by_hijri_year(year: i32) -> Result<Value, String> {
+  let adjustment = get_hijri_adjustment()?;
+  let mut holidays = Vec::new();
+
+  for (month, day, name) in HOLIDAYS {
+    let hijri = HijriDate { year, month, day };
+    if let Some(date) = adjustment.to_gregorian(&hijri) {
+      let mut entry = hijri.to_json();
+      entry["gregorian_date"] = json!(date.format("%d-%m-%Y").to_string());
+      entry["holiday"] = json!(name);
+      holidays.push(entry);
     }
   }
 
-  // Return the array of Gregorian dates with holidays
-  Ok(holiday_days)
+  Ok(Value::Array(holidays))
+}
+
+// get the eclipses seen in the coming years, from the detected location unless coordinates are given
+#[command]
+async fn get_upcoming_eclipses(app: AppHandle, years: Option<u32>, latitude: Option<f64>, longitude: Option<f64>, timezone: Option<String>) -> Result<Value, String> {
+  let years = years.unwrap_or(3);
+  if !(1..=20).contains(&years) {
+    return Err("Years must be between 1 and 20.".to_string());
+  }
+
+  let (coordinates, clock) = resolve_coordinates(app, latitude, longitude, None, timezone).await?;
+  let today = clock.today();
+  let end = today + Duration::days(i64::from(years) * 365);
+
+  let upcoming: Vec<Value> = eclipses_between(today, end, &coordinates, clock)
+    .into_iter()
+    .map(|(date, mut eclipse)| {
+      eclipse["date"] = json!(date.format("%Y-%m-%d").to_string());
+      eclipse
+    })
+    .collect();
+
+  Ok(Value::Array(upcoming))
 }
 
 // get hijri date
 #[command]
-async fn get_today_hijri_date() -> Result<Value, String> {
-  let timestamp = get_local_time();
-  let datetime: DateTime<FixedOffset> = DateTime::parse_from_rfc3339(timestamp.as_str()).unwrap();
-  let date = datetime.format("%d-%m-%Y").to_string();
-  
-  let url = format!("http://api.aladhan.com/v1/gToH/{}", date);
-  let response = reqwest::get(&url).await.unwrap();
-  let req: Value = response.json().await.unwrap();
-  
-  if let Some(hijri) = req["data"]["hijri"].as_object() {
-    let mut processed_entry = json!({
-      "day": hijri.get("day").cloned().unwrap_or(Value::Null),
-      "month": hijri.get("month").and_then(|m| m.get("en")).cloned().unwrap_or(Value::Null),
-      "year": hijri.get("year").cloned().unwrap_or(Value::Null),
-    });
+async fn get_today_hijri_date(app: AppHandle) -> Result<Value, String> {
+  let today = location_clock(app).await.today();
+  let hijri = get_hijri_adjustment()?.to_hijri(today);
+
+  let hijri_json = hijri.to_json();
+  let mut processed_entry = json!({
+    "day": hijri_json["day"],
+    "month": hijri_json["month"],
+    "year": hijri_json["year"],
+  });
 
-    // Check if the entry contains any holidays
-    if let Some(holidays) = hijri.get("holidays").and_then(|h| h.as_array()) {
-      if !holidays.is_empty() {
-        processed_entry["holiday"] = json!(holidays[0]);
-      }
-    }
+  // Check if the day has any holidays
+  let holidays = hijri_holidays(&hijri);
+  if !holidays.is_empty() {
+    processed_entry["holiday"] = json!(holidays[0]);
+  }
+
+  Ok(processed_entry)
+}
 
-    return Ok(processed_entry);
+// get location coordinates
+fn location_coordinates(location: &Location) -> Result<Coordinates, String> {
+  let latitude = location.latitude.parse::<f64>().map_err(|e| format!("Unexpected error at parsing latitude: {}", e))?;
+  let longitude = location.longitude.parse::<f64>().map_err(|e| format!("Unexpected error at parsing longitude: {}", e))?;
+  Ok(Coordinates { latitude, longitude, elevation: location.elevation })
+}
+
+// compute the prayer schedule of a date on a clock
+fn prayer_schedule(date: NaiveDate, coordinates: &Coordinates, settings: &PrayerSettings, adjustment: &HijriAdjustment, clock: LocationClock) -> DailySchedule {
+  // Compute the day's times and the next day's for the night that follows, both on the day's base offset
+  let utc_offset = clock.utc_offset(date);
+  let today_times = compute_prayer_times(date, coordinates, utc_offset, settings, adjustment);
+  let tomorrow_times = compute_prayer_times(date + Duration::days(1), coordinates, utc_offset, settings, adjustment);
+
+  // Move the times falling after a daylight saving switch to the new clock
+  let mut schedule = DailySchedule::new(today_times, &tomorrow_times);
+  schedule.adjust(&|hours| clock.correction(date, utc_offset, hours));
+  schedule
+}
+
+// compute the prayer schedules of every date in a range of at most a year
+fn prayer_schedules(start_date: NaiveDate, end_date: NaiveDate, coordinates: &Coordinates, settings: &PrayerSettings, adjustment: &HijriAdjustment, clock: LocationClock) -> Result<Vec<(NaiveDate, DailySchedule)>, String> {
+  if end_date < start_date || (end_date - start_date).num_days() > 366 {
+    return Err("Date range must be ordered and at most one year long.".to_string());
+  }
+
+  let mut schedules = Vec::new();
+  let mut date = start_date;
+  while date <= end_date {
+    schedules.push((date, prayer_schedule(date, coordinates, settings, adjustment, clock)));
+    date += Duration::days(1);
   }
 
-  Err("Unexpected data format at getting today's Hijri date.".to_string())
+  Ok(schedules)
 }
 
-// get this day data
+// open database with settings table
+fn open_settings() -> Result<Connection, String> {
+  let conn = Connection::open("prayer_tracker.db").map_err(|e| format!("Unexpected error at opening database: {}", e))?;
+  create_settings_table(&conn).map_err(|e| format!("Unexpected error at creating settings table: {}", e))?;
+  Ok(conn)
+}
+
+// get prayer settings
 #[command]
-async fn get_this_day_data() -> Result<Value, String> {
-  // Get the location (latitude and longitude)
-  let location = get_location().await.map_err(|e| format!("Unexpected error at parsing location for this day data: {}", e.to_string()))?;
-  let lat = location.latitude;
-  let lon = location.longitude;
+fn get_prayer_settings() -> Result<PrayerSettings, String> {
+  let conn = open_settings()?;
+  load_prayer_settings(&conn).map_err(|e| format!("Unexpected error at loading prayer settings: {}", e))
+}
 
-  // Get the current year and month
-  let timestamp = get_local_time();
-  let datetime: DateTime<FixedOffset> = DateTime::parse_from_rfc3339(timestamp.as_str()).map_err(|e| format!("Unexpected error at parsing date for this day data: {}", e.to_string()))?;
-  let date = datetime.format("%d-%m-%Y").to_string();
+// get calculation methods
+#[command]
+fn get_calculation_methods() -> Value {
+  let methods: Vec<Value> = CalculationMethod::ALL.iter().map(|method| json!({
+    "id": method,
+    "name": method.name(),
+    "parameters": method.parameters(),
+  })).collect();
+
+  Value::Array(methods)
+}
 
-  // Construct the URL for the API request
-  let url = format!("https://api.aladhan.com/v1/timings/{}?latitude={}&longitude={}", date, lat, lon);
-  
-  // Fetch the response from the API
-  let response = reqwest::get(&url).await.map_err(|e| format!("Unexpected error at fetching this day data: {}", e.to_string()))?;
-  let req: Value = response.json().await.map_err(|e| format!("Unexpected error at parsing this day data: {}", e.to_string()))?;
+// set calculation method
+#[command]
+fn set_calculation_method(method: CalculationMethod) -> Result<(), String> {
+  let conn = open_settings()?;
+  let mut settings = load_prayer_settings(&conn).map_err(|e| format!("Unexpected error at loading prayer settings: {}", e))?;
+  settings.method = method;
+  save_prayer_settings(&conn, &settings).map_err(|e| format!("Unexpected error at saving calculation method: {}", e))
+}
 
-  Ok(req)
+// set asr juristic school
+#[command]
+fn set_asr_school(school: AsrSchool) -> Result<(), String> {
+  let conn = open_settings()?;
+  let mut settings = load_prayer_settings(&conn).map_err(|e| format!("Unexpected error at loading prayer settings: {}", e))?;
+  settings.asr_school = school;
+  save_prayer_settings(&conn, &settings).map_err(|e| format!("Unexpected error at saving asr school: {}", e))
 }
 
-// get this month prayer times
+// set high latitude rule
 #[command]
-async fn get_prayer_times_this_month() -> Result<Value, String> {
-  // Call the async function and await its result
-  let req = get_this_month_data().await?;
+fn set_high_latitude_rule(rule: HighLatitudeRule) -> Result<(), String> {
+  let conn = open_settings()?;
+  let mut settings = load_prayer_settings(&conn).map_err(|e| format!("Unexpected error at loading prayer settings: {}", e))?;
+  settings.high_latitude_rule = rule;
+  save_prayer_settings(&conn, &settings).map_err(|e| format!("Unexpected error at saving high latitude rule: {}", e))
+}
 
-  // Check if the response contains data
-  let data = req["data"].as_array().ok_or("No data found in API response for this month at prayer times this month.")?;
-
-  // Initialize an array to hold all prayer times for the month
-  let mut prayer_times_array = Vec::new();
-
-  // Iterate over each day in the response and extract the relevant prayer times
-  for day in data {
-    if let Some(timings) = day["timings"].as_object() {
-      let selected_timings = json!({
-        "Fajr": timings.get("Fajr").cloned().unwrap_or(Value::Null),
-        "Dhuhr": timings.get("Dhuhr").cloned().unwrap_or(Value::Null),
-        "Asr": timings.get("Asr").cloned().unwrap_or(Value::Null),
-        "Maghrib": timings.get("Maghrib").cloned().unwrap_or(Value::Null),
-        "Isha": timings.get("Isha").cloned().unwrap_or(Value::Null),
-      });
-      prayer_times_array.push(selected_timings);
+// set per prayer minute offsets
+#[command]
+fn set_prayer_offsets(offsets: PrayerOffsets) -> Result<(), String> {
+  let conn = open_settings()?;
+  let mut settings = load_prayer_settings(&conn).map_err(|e| format!("Unexpected error at loading prayer settings: {}", e))?;
+  settings.offsets = offsets;
+  save_prayer_settings(&conn, &settings).map_err(|e| format!("Unexpected error at saving prayer offsets: {}", e))
+}
+
+// set rounding policy
+#[command]
+fn set_rounding_policy(rounding: RoundingPolicy) -> Result<(), String> {
+  let conn = open_settings()?;
+  let mut settings = load_prayer_settings(&conn).map_err(|e| format!("Unexpected error at loading prayer settings: {}", e))?;
+  settings.rounding = rounding;
+  save_prayer_settings(&conn, &settings).map_err(|e| format!("Unexpected error at saving rounding policy: {}", e))
+}
+
+// get coordinates, clock and saved settings of the user location
+async fn location_prayer_context(app: AppHandle) -> Result<(Coordinates, LocationClock, PrayerSettings), String> {
+  let location = get_location(app).await.map_err(|e| format!("Unexpected error at parsing location for prayer times: {}", e))?;
+  let coordinates = location_coordinates(&location)?;
+  let clock = LocationClock::from_timezone(&location.timezone);
+  let settings = get_prayer_settings()?;
+
+  Ok((coordinates, clock, settings))
+}
+
+// get coordinates, clock and settings from explicit values, the saved settings unless given
+fn explicit_prayer_context(latitude: f64, longitude: f64, timezone: &str, elevation: Option<f64>, settings: Option<PrayerSettings>) -> Result<(Coordinates, LocationClock, PrayerSettings), String> {
+  if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+    return Err("Coordinates out of range.".to_string());
+  }
+  let tz = timezone.parse::<Tz>().map_err(|_| format!("Unknown timezone {}.", timezone))?;
+  let settings = match settings {
+    Some(settings) => settings,
+    None => get_prayer_settings()?,
+  };
+
+  Ok((Coordinates { latitude, longitude, elevation }, LocationClock::Zone(tz), settings))
+}
+
+// get the full prayer schedule of every date in a range at the given coordinates and timezone
+#[command]
+fn get_prayer_schedule_range(start_date: String, end_date: String, latitude: f64, longitude: f64, timezone: String, elevation: Option<f64>, settings: Option<PrayerSettings>) -> Result<Value, String> {
+  let start_date = NaiveDate::parse_from_str(&start_date, "%Y-%m-%d").map_err(|e| format!("Invalid start date format: {}", e))?;
+  let end_date = NaiveDate::parse_from_str(&end_date, "%Y-%m-%d").map_err(|e| format!("Invalid end date format: {}", e))?;
+  let (coordinates, clock, settings) = explicit_prayer_context(latitude, longitude, &timezone, elevation, settings)?;
+  let adjustment = get_hijri_adjustment()?;
+
+  let schedules = prayer_schedules(start_date, end_date, &coordinates, &settings, &adjustment, clock)?
+    .into_iter()
+    .map(|(date, schedule)| {
+      let mut entry = schedule.to_json(settings.rounding);
+      entry["date"] = json!(date.format("%Y-%m-%d").to_string());
+      entry
+    })
+    .collect();
+
+  Ok(Value::Array(schedules))
+}
+
+// get the full prayer schedule of a date at the given coordinates and timezone
+#[command]
+fn get_prayer_schedule(date: String, latitude: f64, longitude: f64, timezone: String, elevation: Option<f64>, settings: Option<PrayerSettings>) -> Result<Value, String> {
+  let schedules = get_prayer_schedule_range(date.clone(), date, latitude, longitude, timezone, elevation, settings)?;
+  Ok(schedules[0].clone())
+}
+
+// get the intermediate values behind the prayer times of a date, today without one, at the given
+// place or at the user location without one
+#[command]
+async fn get_prayer_diagnostics(app: AppHandle, date: Option<String>, place: Option<Place>, settings: Option<PrayerSettings>) -> Result<Value, String> {
+  let explicit = place.is_some();
+  let resolved_before = if explicit { None } else { cached_location(&app)?.map(|cached| cached.resolved_at) };
+  let (coordinates, clock, settings) = match place {
+    Some(place) => explicit_prayer_context(place.latitude, place.longitude, &place.timezone, place.elevation, settings)?,
+    None => {
+      let (coordinates, clock, saved_settings) = location_prayer_context(app.clone()).await?;
+      (coordinates, clock, settings.unwrap_or(saved_settings))
+    },
+  };
+
+  // Note where the location came from, served from the cache or looked up by this call
+  let location_source = match cached_location(&app)? {
+    _ if explicit => json!({ "source": "explicit" }),
+    Some(cached) => json!({
+      "source": if resolved_before == Some(cached.resolved_at) { "cache" } else { "lookup" },
+      "origin": if from_ip_lookup(&cached.location) { "ip_lookup" } else { "saved_location" },
+      "resolved_at": cached.resolved_at,
+    }),
+    None => json!({ "source": "lookup" }),
+  };
+  let date = match date {
+    Some(date) => NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|e| format!("Invalid date format: {}", e))?,
+    None => clock.today(),
+  };
+
+  // Recompute the day with the local engine, keeping every stage
+  let utc_offset = clock.utc_offset(date);
+  let diagnostics = prayer_diagnostics(date, &coordinates, utc_offset, &settings, &get_hijri_adjustment()?);
+  let mut result = diagnostics.to_json(settings.rounding, &|hours| clock.correction(date, utc_offset, hours));
+  result["date"] = json!(date.format("%Y-%m-%d").to_string());
+  result["location"] = json!({
+    "latitude": coordinates.latitude,
+    "longitude": coordinates.longitude,
+    "elevation": coordinates.elevation,
+    "utc_offset": utc_offset,
+  });
+  result["location_source"] = location_source;
+
+  // Every time is recomputed by the local engine, aladhan is only asked when comparing methods
+  result["times_source"] = json!("local_engine");
+  result["settings"] = json!({
+    "method": settings.method.name(),
+    "asr_school": settings.asr_school,
+    "high_latitude_rule": settings.high_latitude_rule,
+    "offsets": settings.offsets,
+    "rounding": settings.rounding,
+  });
+
+  Ok(result)
+}
+
+// the five daily prayers of every date in a range, in hours on the clock
+fn fard_prayer_hours(start_date: NaiveDate, end_date: NaiveDate, coordinates: &Coordinates, settings: &PrayerSettings, adjustment: &HijriAdjustment, clock: LocationClock) -> Result<Vec<(NaiveDate, [f64; 5])>, String> {
+  let days = prayer_schedules(start_date, end_date, coordinates, settings, adjustment, clock)?
+    .into_iter()
+    .map(|(date, schedule)| (date, schedule.prayers.fard_prayers().map(|(_, hours)| hours)))
+    .collect();
+
+  Ok(days)
+}
+
+// fetch the five daily prayers of a month from aladhan with the same method, school and high latitude rule
+async fn aladhan_prayer_hours(year: i32, month: u32, coordinates: &Coordinates, settings: &PrayerSettings, clock: LocationClock) -> Result<Vec<(NaiveDate, [f64; 5])>, String> {
+  let mut url = format!(
+    "https://api.aladhan.com/v1/calendar/{}/{}?latitude={}&longitude={}&method={}&school={}",
+    year, month, coordinates.latitude, coordinates.longitude, settings.method.aladhan_id(), settings.asr_school.aladhan_id()
+  );
+  if let Some(rule) = settings.high_latitude_rule.aladhan_id() {
+    url.push_str(&format!("&latitudeAdjustmentMethod={}", rule));
+  }
+  if let LocationClock::Zone(tz) = clock {
+    url.push_str(&format!("&timezonestring={}", tz.name()));
+  }
+
+  let response = reqwest::get(&url).await.map_err(|e| format!("Unexpected error at fetching aladhan calendar: {}", e))?;
+  let req: Value = response.json().await.map_err(|e| format!("Unexpected error at parsing aladhan calendar: {}", e))?;
+  let days = req["data"].as_array().ok_or("Unexpected data format at aladhan calendar.")?;
+
+  let mut result = Vec::new();
+  for day in days {
+    let date = day["date"]["gregorian"]["date"].as_str().unwrap_or_default();
+    let date = NaiveDate::parse_from_str(date, "%d-%m-%Y").map_err(|e| format!("Unexpected error at parsing aladhan date: {}", e))?;
+
+    // Timings carry the zone after the time, as in "04:12 (+03)"
+    let mut hours = [f64::NAN; 5];
+    for (index, name) in ["Fajr", "Dhuhr", "Asr", "Maghrib", "Isha"].iter().enumerate() {
+      let timing = day["timings"][name].as_str().unwrap_or_default();
+      let time = NaiveTime::parse_from_str(timing.get(..5).unwrap_or(timing), "%H:%M").map_err(|e| format!("Unexpected error at parsing aladhan timing: {}", e))?;
+      hours[index] = time.num_seconds_from_midnight() as f64 / 3600.0;
+    }
+    result.push((date, hours));
+  }
+
+  Ok(result)
+}
+
+// difference in minutes between two clock times after rounding, across midnight the short way
+fn minute_difference(hours: f64, reference: f64, rounding: RoundingPolicy) -> Option<f64> {
+  let seconds = rounding.round_seconds(hours)? as f64;
+  let reference = rounding.round_seconds(reference)? as f64;
+  Some(((seconds - reference) / 60.0 + 720.0).rem_euclid(1440.0) - 720.0)
+}
+
+// compare the timetable of a month under every calculation method and asr school, in minutes from a chosen
+// method, or from aladhan with the saved method when no reference is given and it can be reached
+#[command]
+async fn compare_calculation_methods(app: AppHandle, month: u32, year: i32, place: Option<Place>, reference_method: Option<CalculationMethod>, reference_school: Option<AsrSchool>) -> Result<Value, String> {
+  let (coordinates, clock, settings) = match place {
+    Some(place) => explicit_prayer_context(place.latitude, place.longitude, &place.timezone, place.elevation, None)?,
+    None => location_prayer_context(app).await?,
+  };
+  let adjustment = get_hijri_adjustment()?;
+  let first_day = NaiveDate::from_ymd_opt(year, month, 1).ok_or("Invalid month at comparing calculation methods.")?;
+  let last_day = (first_day + Duration::days(31)).with_day(1).ok_or("Invalid month at comparing calculation methods.")? - Duration::days(1);
+
+  // Compare the presets alone, without the user offsets
+  let preset = |method: CalculationMethod, asr_school: AsrSchool| PrayerSettings {
+    method,
+    asr_school,
+    offsets: PrayerOffsets::default(),
+    ..settings.clone()
+  };
+
+  // Use the chosen method as the reference, aladhan otherwise and the saved method when offline
+  let reference_settings = preset(reference_method.unwrap_or(settings.method), reference_school.unwrap_or(settings.asr_school));
+  let aladhan = match reference_method {
+    Some(_) => None,
+    None => Some(aladhan_prayer_hours(year, month, &coordinates, &reference_settings, clock).await),
+  };
+  let (source, fallback, reference) = match aladhan {
+    Some(Ok(days)) => ("aladhan", None, days),
+    Some(Err(e)) => ("local_engine", Some(e), fard_prayer_hours(first_day, last_day, &coordinates, &reference_settings, &adjustment, clock)?),
+    None => ("local_engine", None, fard_prayer_hours(first_day, last_day, &coordinates, &reference_settings, &adjustment, clock)?),
+  };
+
+  let prayers = ["Fajr", "Dhuhr", "Asr", "Maghrib", "Isha"];
+  let mut comparisons = Vec::new();
+  for method in CalculationMethod::ALL {
+    for asr_school in [AsrSchool::Standard, AsrSchool::Hanafi] {
+      let days = fard_prayer_hours(first_day, last_day, &coordinates, &preset(method, asr_school), &adjustment, clock)?;
+
+      // Differences of each day against the reference of the same date
+      let mut differences: Vec<Vec<f64>> = vec![Vec::new(); prayers.len()];
+      let mut day_entries = Vec::new();
+      for (date, hours) in &days {
+        let mut entry = json!({ "date": date.format("%Y-%m-%d").to_string() });
+        let reference_hours = reference.iter().find(|(reference_date, _)| reference_date == date).map(|(_, hours)| hours);
+        for (index, name) in prayers.iter().enumerate() {
+          let difference = reference_hours.and_then(|reference_hours| minute_difference(hours[index], reference_hours[index], settings.rounding));
+          if let Some(difference) = difference {
+            differences[index].push(difference);
+          }
+          entry[*name] = json!(difference);
+        }
+        day_entries.push(entry);
+      }
+
+      // Range and mean of the differences over the month
+      let mut summary = json!({});
+      for (index, name) in prayers.iter().enumerate() {
+        let values = &differences[index];
+        summary[*name] = if values.is_empty() {
+          Value::Null
+        } else {
+          json!({
+            "min": values.iter().cloned().fold(f64::INFINITY, f64::min),
+            "max": values.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+            "mean": values.iter().sum::<f64>() / values.len() as f64,
+          })
+        };
+      }
+
+      comparisons.push(json!({
+        "method": method,
+        "name": method.name(),
+        "asr_school": asr_school,
+        "reference": method == reference_settings.method && asr_school == reference_settings.asr_school,
+        "summary": summary,
+        "days": day_entries,
+      }));
     }
   }
 
-  // Return the array of prayer times as a JSON value
+  // Reference times as published, for the frontend to show next to the differences
+  let reference_days: Vec<Value> = reference.iter().map(|(date, hours)| {
+    let mut entry = json!({ "date": date.format("%Y-%m-%d").to_string() });
+    for (index, name) in prayers.iter().enumerate() {
+      entry[*name] = json!(format_time(hours[index], settings.rounding));
+    }
+    entry
+  }).collect();
+
+  Ok(json!({
+    "month": month,
+    "year": year,
+    "location": {
+      "latitude": coordinates.latitude,
+      "longitude": coordinates.longitude,
+      "elevation": coordinates.elevation,
+    },
+    "reference": {
+      "source": source,
+      "method": reference_settings.method,
+      "name": reference_settings.method.name(),
+      "asr_school": reference_settings.asr_school,
+      "high_latitude_rule": reference_settings.high_latitude_rule,
+      "fallback_reason": fallback,
+      "days": reference_days,
+    },
+    "methods": comparisons,
+  }))
+}
+
+// get the timetable of a gregorian or hijri year with hijri dates
+// at the given place, or at the user location without one
+async fn yearly_timetable(app: AppHandle, year: i32, calendar: Option<TimetableCalendar>, place: Option<Place>, settings: Option<PrayerSettings>) -> Result<(Vec<TimetableRow>, RoundingPolicy), String> {
+  let (coordinates, clock, settings) = match place {
+    Some(place) => explicit_prayer_context(place.latitude, place.longitude, &place.timezone, place.elevation, settings)?,
+    None => {
+      let (coordinates, clock, saved_settings) = location_prayer_context(app).await?;
+      (coordinates, clock, settings.unwrap_or(saved_settings))
+    },
+  };
+
+  // Get the gregorian days of the year
+  let adjustment = get_hijri_adjustment()?;
+  let (start_date, end_date) = calendar
+    .unwrap_or_default()
+    .year_range(year, &adjustment)
+    .ok_or(format!("Year {} is out of range for the timetable.", year))?;
+
+  // Compute every day of the year with its hijri date
+  let rows: Vec<TimetableRow> = prayer_schedules(start_date, end_date, &coordinates, &settings, &adjustment, clock)?
+    .into_iter()
+    .map(|(date, schedule)| TimetableRow { date, hijri: adjustment.to_hijri(date), schedule })
+    .collect();
+
+  Ok((rows, settings.rounding))
+}
+
+// get the yearly timetable
+#[command]
+async fn get_yearly_timetable(app: AppHandle, year: i32, calendar: Option<TimetableCalendar>, place: Option<Place>, settings: Option<PrayerSettings>) -> Result<Value, String> {
+  let (rows, rounding) = yearly_timetable(app, year, calendar, place, settings).await?;
+  Ok(Value::Array(rows.iter().map(|row| row.to_json(rounding)).collect()))
+}
+
+// get the yearly timetable as csv text, the frontend saves it where the user picks
+#[command]
+async fn get_yearly_timetable_csv(app: AppHandle, year: i32, calendar: Option<TimetableCalendar>, place: Option<Place>, settings: Option<PrayerSettings>) -> Result<String, String> {
+  let (rows, rounding) = yearly_timetable(app, year, calendar, place, settings).await?;
+  Ok(timetable_csv(&rows, rounding))
+}
+
+// get this month prayer times
+#[command]
+async fn get_prayer_times_this_month(app: AppHandle) -> Result<Value, String> {
+  let (coordinates, clock, settings) = location_prayer_context(app).await?;
+
+  // Get the first and last day of the current month
+  let today = clock.today();
+  let first_day = NaiveDate::from_ymd_opt(today.year(), today.month(), 1).ok_or("Invalid date at prayer times this month.")?;
+  let last_day = (first_day + Duration::days(31)).with_day(1).ok_or("Invalid date at prayer times this month.")? - Duration::days(1);
+
+  // Return the prayer times of each day of the month
+  let prayer_times_array = prayer_schedules(first_day, last_day, &coordinates, &settings, &get_hijri_adjustment()?, clock)?
+    .into_iter()
+    .map(|(_, schedule)| schedule.prayers.to_json(settings.rounding))
+    .collect();
+
   Ok(Value::Array(prayer_times_array))
 }
 
+// compute today's schedule at the user location with the saved settings
+async fn schedule_today(app: AppHandle) -> Result<(DailySchedule, PrayerSettings, LocationClock), String> {
+  let (coordinates, clock, settings) = location_prayer_context(app).await?;
+  let schedule = prayer_schedule(clock.today(), &coordinates, &settings, &get_hijri_adjustment()?, clock);
+
+  Ok((schedule, settings, clock))
+}
+
 // get this day prayer times
 #[command]
-async fn get_prayer_times_this_day() -> Result<Value, String> {
-  let req = get_this_day_data().await?;
-
-  // Extract the prayer times for the day
-  if let Some(timings) = req["data"]["timings"].as_object() {
-    let selected_timings = json!({
-      "Fajr": timings.get("Fajr"),
-      "Dhuhr": timings.get("Dhuhr"),
-      "Asr": timings.get("Asr"),
-      "Maghrib": timings.get("Maghrib"),
-      "Isha": timings.get("Isha"),
-    });
+async fn get_prayer_times_this_day(app: AppHandle) -> Result<Value, String> {
+  let (schedule, settings, _) = schedule_today(app).await?;
 
-    // Return the prayer times as a JSON object
-    return Ok(selected_timings);
-  }
+  // Return the prayer times as a JSON object
+  Ok(schedule.prayers.to_json(settings.rounding))
+}
 
-  Err("No data found in API response for this day at prayer times this day.".to_string())
+// get this day extended schedule
+#[command]
+async fn get_daily_schedule_this_day(app: AppHandle) -> Result<Value, String> {
+  let (schedule, settings, _) = schedule_today(app).await?;
+  Ok(schedule.to_json(settings.rounding))
 }
 
-// get nearest prayer
+// get this day forbidden prayer times
 #[command]
-async fn get_nearest_prayer() -> Result<String, String> {
-  // Get the prayer times for the day
-  let prayer_times = get_prayer_times_this_day().await?;
+async fn get_forbidden_times_this_day(app: AppHandle) -> Result<Value, String> {
+  let (schedule, settings, _) = schedule_today(app).await?;
+
+  let windows: Vec<Value> = schedule.forbidden_windows().iter().map(|(name, start, end)| json!({
+    "name": name,
+    "start": format_time(*start, settings.rounding),
+    "end": format_time(*end, settings.rounding),
+  })).collect();
+
+  Ok(Value::Array(windows))
+}
+
+// check if now is a forbidden prayer time
+#[command]
+async fn is_forbidden_time_now(app: AppHandle) -> Result<Value, String> {
+  let (schedule, settings, clock) = schedule_today(app).await?;
 
   // Get the current time
-  let current_time = local_clock()?;
+  let current_time = clock.now().format("%H:%M:%S").to_string();
   let current_time = NaiveTime::parse_from_str(&current_time, "%H:%M:%S")
-    .map_err(|e| format!("Unexpected error at parsing current time for nearest prayer: {}", e.to_string()))?;
-
-  // List of prayer names in correct order
-  let prayer_names = ["Fajr", "Dhuhr", "Asr", "Maghrib", "Isha"];
-
-  // Initialize nearest prayer and time difference
-  let mut nearest_prayer = "";
-  let mut time_diff = Duration::hours(24);
-  let tolerance = Duration::minutes(35);
-
-  // Iterate over each prayer and calculate time difference
-  for prayer in prayer_names.iter() {
-    if let Some(time_str) = prayer_times[prayer].as_str() {
-      // Parse time string
-      let prayer_time = NaiveTime::parse_from_str(time_str, "%H:%M")
-        .map_err(|e| format!("Unexpected error at parsing time for nearest prayer: {}", e.to_string()))?;
-
-      // Calculate time difference
-      let diff = if prayer_time > current_time {
-        prayer_time - current_time
-      } else {
-        let passed_time = current_time - prayer_time;
-        if passed_time <= tolerance {
-          // If prayer time has just passed within tolerance, consider it as nearest
-          passed_time
-        } else {
-          // Add 24 hours if the prayer time is before current time and outside tolerance
-          Duration::hours(24) - passed_time
-        }
-      };
-
-      // Update nearest prayer and time difference
-      if diff < time_diff {
-        time_diff = diff;
-        nearest_prayer = prayer;
+    .map_err(|e| format!("Unexpected error at parsing current time for forbidden time: {}", e))?;
+
+  // Find the window containing the current time
+  for (name, start, end) in schedule.forbidden_windows() {
+    if let (Some(start), Some(end)) = (clock_time(start, settings.rounding), clock_time(end, settings.rounding)) {
+      if start <= current_time && current_time < end {
+        return Ok(json!({
+          "forbidden": true,
+          "name": name,
+          "end": end.format("%H:%M:%S").to_string(),
+        }));
       }
     }
   }
 
-  Ok(nearest_prayer.to_string())
+  Ok(json!({ "forbidden": false }))
+}
+
+// nearest and next prayer of a schedule at the current time on a location's clock
+fn nearest_prayer_now(schedule: &DailySchedule, rounding: RoundingPolicy, clock: LocationClock) -> Result<Option<NearestPrayer>, String> {
+  // Get the current time
+  let current_time = clock.now().format("%H:%M:%S").to_string();
+  let current_time = NaiveTime::parse_from_str(&current_time, "%H:%M:%S")
+    .map_err(|e| format!("Unexpected error at parsing current time for nearest prayer: {}", e))?;
+
+  Ok(schedule.prayers.nearest(rounding, current_time))
+}
+
+// get nearest prayer
+#[command]
+async fn get_nearest_prayer(app: AppHandle) -> Result<String, String> {
+  // Get the prayer times for the day
+  let (schedule, settings, clock) = schedule_today(app).await?;
+  let nearest = nearest_prayer_now(&schedule, settings.rounding, clock)?;
+
+  Ok(nearest.map_or("", |nearest| nearest.nearest).to_string())
 }
 
 // get time until next prayer
 #[command]
-async fn get_time_until_next_prayer() -> Result<String, String> {
+async fn get_time_until_next_prayer(app: AppHandle) -> Result<String, String> {
   // Get the prayer times for the day
-  let prayer_times = get_prayer_times_this_day().await?;
+  let (schedule, settings, clock) = schedule_today(app).await?;
+  let nearest = nearest_prayer_now(&schedule, settings.rounding, clock)?;
 
-  // Get the current time
-  let current_time = local_clock()?;
-  let current_time = NaiveTime::parse_from_str(&current_time, "%H:%M:%S")
-    .map_err(|e| format!("Unexpected error at parsing current time for time until next prayer: {}", e.to_string()))?;
-
-  // List of prayer names in correct order
-  let prayer_names = ["Fajr", "Dhuhr", "Asr", "Maghrib", "Isha"];
-
-  // Initialize nearest prayer and time difference
-  let mut time_diff = Duration::hours(24);
-  let tolerance = Duration::minutes(35);
-
-  // Iterate over each prayer and calculate time difference
-  for prayer in prayer_names.iter() {
-    if let Some(time_str) = prayer_times[prayer].as_str() {
-      // Parse time string
-      let prayer_time = NaiveTime::parse_from_str(time_str, "%H:%M")
-        .map_err(|e| format!("Unexpected error at parsing time for time until next prayer: {}", e.to_string()))?;
-
-      // Calculate time difference
-      let diff = if prayer_time > current_time {
-        prayer_time - current_time
-      } else {
-        let passed_time = current_time - prayer_time;
-        if passed_time <= tolerance {
-          // If prayer time has just passed within tolerance, consider it as current
-          Duration::zero()
-        } else {
-          // Add 24 hours if the prayer time is before current time and outside tolerance
-          Duration::hours(24) - passed_time
-        }
-      };
+  // "Now" within the tolerance after a prayer time, otherwise the countdown
+  Ok(nearest.map_or_else(|| format_countdown(Duration::hours(24)), |nearest| nearest.countdown()))
+}
 
-      // Update nearest prayer and time difference
-      if diff < time_diff {
-        time_diff = diff;
-      }
+// get the local time and the current and next prayer at every saved location
+#[command]
+fn get_world_prayer_times() -> Result<Value, String> {
+  let settings = get_prayer_settings()?;
+  let adjustment = get_hijri_adjustment()?;
+  let mut locations = Vec::new();
+
+  for saved in list_saved_locations()? {
+    // Compute today's schedule on the location's own clock
+    let clock = LocationClock::from_timezone(&saved.timezone);
+    let coordinates = Coordinates { latitude: saved.latitude, longitude: saved.longitude, elevation: saved.elevation };
+    let schedule = prayer_schedule(clock.today(), &coordinates, &settings, &adjustment, clock);
+    let nearest = nearest_prayer_now(&schedule, settings.rounding, clock)?;
+    let now = clock.now();
+
+    locations.push(json!({
+      "id": saved.id,
+      "name": saved.name,
+      "city": saved.city,
+      "country": saved.country,
+      "timezone": saved.timezone,
+      "active": saved.active,
+      "date": now.format("%Y-%m-%d").to_string(),
+      "local_time": now.format("%H:%M:%S").to_string(),
+      "nearest": nearest.as_ref().map(|nearest| nearest.nearest),
+      "current": nearest.as_ref().and_then(|nearest| nearest.current),
+      "next": nearest.as_ref().map(|nearest| nearest.next),
+      "next_time": nearest.as_ref().map(|nearest| nearest.next_time.format("%H:%M:%S").to_string()),
+      "countdown": nearest.as_ref().map(|nearest| nearest.countdown()),
+    }));
+  }
+
+  Ok(Value::Array(locations))
+}
+
+// search the bundled airports by code, city or name
+#[command]
+fn search_airports(query: String, limit: Option<usize>) -> Vec<Airport> {
+  search_airport_list(&query, limit.unwrap_or(20))
+}
+
+// location of a point, named after the nearest bundled city when close enough,
+// otherwise on the nautical timezone of its longitude
+fn point_location(latitude: f64, longitude: f64) -> (Location, Option<(City, f64)>) {
+  let nearest = nearest_city(latitude, longitude);
+  let mut location = Location {
+    ip: String::new(),
+    latitude: format!("{:.4}", latitude),
+    longitude: format!("{:.4}", longitude),
+    elevation: None,
+    city: String::new(),
+    region: String::new(),
+    country: String::new(),
+    timezone: match (longitude / 15.0).round() as i32 {
+      0 => "Etc/GMT".to_string(),
+      zone => format!("Etc/GMT{:+}", -zone),
+    },
+    location: format!("{:.4},{:.4}", latitude, longitude),
+  };
+
+  if let Some((city, km)) = &nearest {
+    if *km <= NEAREST_CITY_KM {
+      location.city = city.name.clone();
+      location.region = city.region.clone();
+      location.country = city.country.clone();
+      location.timezone = city.timezone.clone();
     }
   }
 
-  // Check if we're within 20 minutes after a prayer time
-  if time_diff == Duration::zero() {
-    Ok("Now".to_string())
-  } else {
-    // Convert the duration to hours, minutes, and seconds
-    let hours = time_diff.num_hours();
-    let minutes = (time_diff - Duration::hours(hours)).num_minutes();
-    let seconds = (time_diff - Duration::hours(hours) - Duration::minutes(minutes)).num_seconds();
-    
-    // Format the result
-    let result = format!("-{}:{:02}:{:02}", hours, minutes, seconds);
-    Ok(result)
+  (location, nearest)
+}
+
+// get the prayer times falling during a flight between two bundled airports, the departure
+// time on the departure airport's clock as YYYY-MM-DDTHH:MM
+#[command]
+fn get_flight_prayer_times(departure: String, arrival: String, departure_time: String, duration_minutes: i64, settings: Option<PrayerSettings>) -> Result<Value, String> {
+  let departure = find_airport(&departure).ok_or_else(|| format!("Unknown airport {}.", departure))?;
+  let arrival = find_airport(&arrival).ok_or_else(|| format!("Unknown airport {}.", arrival))?;
+  if !(1..=24 * 60).contains(&duration_minutes) {
+    return Err("Flight duration must be between 1 minute and 24 hours.".to_string());
   }
+  let settings = match settings {
+    Some(settings) => settings,
+    None => get_prayer_settings()?,
+  };
+
+  // Convert the departure time from the departure airport's clock
+  let departure_clock = LocationClock::from_timezone(&departure.timezone);
+  let arrival_clock = LocationClock::from_timezone(&arrival.timezone);
+  let local_departure = NaiveDateTime::parse_from_str(&departure_time, "%Y-%m-%dT%H:%M")
+    .map_err(|e| format!("Invalid departure time format: {}", e))?;
+  let departure_time = departure_clock.instant(local_departure)
+    .ok_or_else(|| format!("Departure time {} does not exist on the departure clock.", local_departure))?;
+
+  let flight = Flight { departure, arrival, departure_time, duration: Duration::minutes(duration_minutes) };
+  let arrival_time = flight.arrival_time();
+
+  // Describe every prayer time with the clocks on board and the region below
+  let prayers: Vec<Value> = flight.prayer_times(&settings, &get_hijri_adjustment()?).iter().map(|prayer| {
+    let (position, nearest) = point_location(prayer.latitude, prayer.longitude);
+    let elapsed = prayer.time - flight.departure_time;
+    json!({
+      "prayer": prayer.prayer,
+      "utc": prayer.time.format("%Y-%m-%d %H:%M").to_string(),
+      "departure_time": departure_clock.at(prayer.time).format("%Y-%m-%d %H:%M").to_string(),
+      "arrival_time": arrival_clock.at(prayer.time).format("%Y-%m-%d %H:%M").to_string(),
+      "local_time": LocationClock::from_timezone(&position.timezone).at(prayer.time).format("%Y-%m-%d %H:%M").to_string(),
+      "elapsed": format!("{}:{:02}", elapsed.num_hours(), elapsed.num_minutes() % 60),
+      "position": position,
+      "nearest_city": nearest.as_ref().map(|(city, _)| city.name.clone()),
+      "nearest_city_km": nearest.as_ref().map(|(_, km)| km.round()),
+    })
+  }).collect();
+
+  Ok(json!({
+    "departure": flight.departure,
+    "arrival": flight.arrival,
+    "distance_km": flight.distance().round(),
+    "departure_time": departure_clock.at(flight.departure_time).format("%Y-%m-%d %H:%M").to_string(),
+    "arrival_time": arrival_clock.at(arrival_time).format("%Y-%m-%d %H:%M").to_string(),
+    "prayers": prayers,
+  }))
+}
+
+// decimal year of a date, as used by the magnetic model
+fn decimal_year(date: NaiveDate) -> f64 {
+  let days_in_year = if NaiveDate::from_ymd_opt(date.year(), 2, 29).is_some() { 366.0 } else { 365.0 };
+  date.year() as f64 + (date.ordinal0() as f64) / days_in_year
+}
+
+// get explicit coordinates, or the detected location when none are given, on the given timezone,
+// or without one on the location's own: the detected timezone or the nearest bundled city's
+async fn resolve_coordinates(app: AppHandle, latitude: Option<f64>, longitude: Option<f64>, elevation: Option<f64>, timezone: Option<String>) -> Result<(Coordinates, LocationClock), String> {
+  let (coordinates, location_timezone) = match (latitude, longitude) {
+    (Some(latitude), Some(longitude)) => {
+      if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return Err("Coordinates out of range.".to_string());
+      }
+      (Coordinates { latitude, longitude, elevation }, point_location(latitude, longitude).0.timezone)
+    },
+    (None, None) => {
+      let location = get_location(app).await.map_err(|e| format!("Unexpected error at parsing location: {}", e))?;
+      (location_coordinates(&location)?, location.timezone)
+    },
+    _ => return Err("Both latitude and longitude are required.".to_string()),
+  };
+
+  let clock = match timezone {
+    Some(timezone) => LocationClock::Zone(timezone.parse::<Tz>().map_err(|_| format!("Unknown timezone {}.", timezone))?),
+    None => LocationClock::from_timezone(&location_timezone),
+  };
+
+  Ok((coordinates, clock))
+}
+
+// get qibla direction and distance, from the detected location unless coordinates are given
+#[command]
+async fn get_qibla_direction(app: AppHandle, latitude: Option<f64>, longitude: Option<f64>, elevation: Option<f64>, timezone: Option<String>) -> Result<Value, String> {
+  let (coordinates, clock) = resolve_coordinates(app, latitude, longitude, elevation, timezone).await?;
+
+  let bearing = qibla_bearing(coordinates.latitude, coordinates.longitude);
+  let distance = kaaba_distance(coordinates.latitude, coordinates.longitude);
+
+  // Correct the bearing for the magnetic declination at the location
+  let model = MagneticModel::bundled()?;
+  let altitude = coordinates.elevation.unwrap_or(0.0) / 1000.0;
+  let declination = model.declination(coordinates.latitude, coordinates.longitude, altitude, decimal_year(clock.today()));
+  let magnetic_bearing = (bearing - declination).rem_euclid(360.0);
+
+  Ok(json!({
+    "latitude": coordinates.latitude,
+    "longitude": coordinates.longitude,
+    "bearing": bearing,
+    "magnetic_bearing": magnetic_bearing,
+    "declination": declination,
+    "distance_km": distance,
+    "magnetic_model": model.name,
+  }))
+}
+
+// get the times a shadow points along the qibla, and when the sun stands over the Kaaba
+// explicit coordinates use the given timezone, or the nearest bundled city's without one
+#[command]
+async fn get_qibla_sun_times(app: AppHandle, start_date: String, end_date: String, latitude: Option<f64>, longitude: Option<f64>, timezone: Option<String>) -> Result<Value, String> {
+  let start_date = NaiveDate::parse_from_str(&start_date, "%Y-%m-%d").map_err(|e| format!("Invalid start date format: {}", e))?;
+  let end_date = NaiveDate::parse_from_str(&end_date, "%Y-%m-%d").map_err(|e| format!("Invalid end date format: {}", e))?;
+  if end_date < start_date || (end_date - start_date).num_days() > 366 {
+    return Err("Date range must be ordered and at most one year long.".to_string());
+  }
+
+  let (coordinates, clock) = resolve_coordinates(app, latitude, longitude, None, timezone).await?;
+  let rounding = get_prayer_settings()?.rounding;
+  let bearing = qibla_bearing(coordinates.latitude, coordinates.longitude);
+
+  // The shadow points away from the qibla when the sun stands on the qibla bearing
+  let mut days = Vec::new();
+  let mut date = start_date;
+  while date <= end_date {
+    let utc_offset = clock.utc_offset(date);
+    let format_times = |times: Vec<f64>| times
+      .into_iter()
+      .map(|time| format_time(time + clock.correction(date, utc_offset, time), rounding))
+      .collect::<Vec<String>>();
+    days.push(json!({
+      "date": date.format("%Y-%m-%d").to_string(),
+      "shadow_away_from_qibla": format_times(sun_azimuth_times(date, coordinates.latitude, coordinates.longitude, utc_offset, bearing)),
+      "shadow_toward_qibla": format_times(sun_azimuth_times(date, coordinates.latitude, coordinates.longitude, utc_offset, (bearing + 180.0) % 360.0)),
+    }));
+    date += Duration::days(1);
+  }
+
+  // Every shadow points away from the Kaaba when the sun stands over it
+  let mut transits = Vec::new();
+  for year in start_date.year()..=end_date.year() {
+    for (date, ut) in sun_over_kaaba(year) {
+      // the correction from a zero base offset is the utc offset at that moment
+      let seconds = ((ut + clock.correction(date, 0.0, ut)) * 3600.0).round() as i64;
+      let local = date.and_hms_opt(0, 0, 0).unwrap_or_default() + Duration::seconds(seconds);
+      transits.push(json!({
+        "date": local.format("%Y-%m-%d").to_string(),
+        "time": local.format("%H:%M").to_string(),
+      }));
+    }
+  }
+
+  Ok(json!({
+    "bearing": bearing,
+    "days": days,
+    "sun_over_kaaba": transits,
+  }))
 }
 
 // get random verse
@@ -621,8 +1718,8 @@ async fn get_time_until_next_prayer() -> Result<String, String> {
 async fn get_random_verse() -> Result<TodayVerse, String> {
   let random_number = generate_random_number().to_string();
   let url = format!("https://api.alquran.cloud/v1/ayah/{}/en.asad", random_number);
-  let response = reqwest::get(&url).await.map_err(|e| format!("Unexpected error at fetching random verse: {}", e.to_string()))?;
-  let req: Value = response.json().await.map_err(|e| format!("Unexpected error at parsing random verse: {}", e.to_string()))?;
+  let response = reqwest::get(&url).await.map_err(|e| format!("Unexpected error at fetching random verse: {}", e))?;
+  let req: Value = response.json().await.map_err(|e| format!("Unexpected error at parsing random verse: {}", e))?;
 
   let data = &req["data"];
   let verse = TodayVerse {
@@ -640,8 +1737,8 @@ async fn get_random_verse() -> Result<TodayVerse, String> {
 #[command]
 async fn get_quran_data() -> Result<QuranData, String> {
   let url = "https://api.alquran.cloud/v1/quran/ar.alafasy";
-  let response = reqwest::get(url).await.map_err(|e| format!("Unexpected error at fetching Quran data: {}", e.to_string()))?;
-  let req: Value = response.json().await.map_err(|e| format!("Unexpected error at parsing Quran data: {}", e.to_string()))?;
+  let response = reqwest::get(url).await.map_err(|e| format!("Unexpected error at fetching Quran data: {}", e))?;
+  let req: Value = response.json().await.map_err(|e| format!("Unexpected error at parsing Quran data: {}", e))?;
 
   // Check if the response contains data
   let data = req["data"]["surahs"].as_array().ok_or("No data found in API response for Quran data.")?;
@@ -665,8 +1762,8 @@ async fn get_quran_data() -> Result<QuranData, String> {
 #[command]
 async fn get_surah_translation(id: String) -> Result<Vec<AyahTranslation>, String> {
   let url = format!("https://api.alquran.cloud/v1/surah/{}/en.asad", id);
-  let response = reqwest::get(&url).await.map_err(|e| format!("Unexpected error at fetching surah translation: {}", e.to_string()))?;
-  let req: Value = response.json().await.map_err(|e| format!("Unexpected error at parsing surah translation: {}", e.to_string()))?;
+  let response = reqwest::get(&url).await.map_err(|e| format!("Unexpected error at fetching surah translation: {}", e))?;
+  let req: Value = response.json().await.map_err(|e| format!("Unexpected error at parsing surah translation: {}", e))?;
 
   // Check if the response contains the array of ayahs
   let data = req["data"]["ayahs"].as_array().ok_or("No ayahs found in API response for surah translation.")?;
@@ -807,6 +1904,8 @@ fn main() {
     Arc::new(AppState {
       current_ayah: Mutex::new(None),
       is_playing: Mutex::new(false),
+      location: Mutex::new(None),
+      location_failure: Mutex::new(None),
     })
   )
   .invoke_handler(tauri::generate_handler![
@@ -814,16 +1913,57 @@ fn main() {
     get_hijri_calendar,
     get_today_hijri_date,
     get_hijri_calendar_by_month,
+    get_crescent_visibility,
+    get_hijri_adjustment,
+    set_hijri_adjustment,
+    set_hijri_month_adjustment,
     check_holidays,
     get_holiday_days,
+    get_holidays_by_hijri_year,
+    get_upcoming_eclipses,
+    get_user_events,
+    add_user_event,
+    update_user_event,
+    delete_user_event,
     get_prayer_times_this_month,
     get_prayer_times_this_day,
+    get_prayer_schedule,
+    get_prayer_schedule_range,
+    get_yearly_timetable,
+    get_yearly_timetable_csv,
+    get_prayer_diagnostics,
+    compare_calculation_methods,
     get_nearest_prayer,
     get_time_until_next_prayer,
+    get_world_prayer_times,
+    search_airports,
+    get_flight_prayer_times,
+    get_daily_schedule_this_day,
+    get_forbidden_times_this_day,
+    is_forbidden_time_now,
+    get_qibla_direction,
+    get_qibla_sun_times,
+    get_prayer_settings,
+    get_calculation_methods,
+    set_calculation_method,
+    set_asr_school,
+    set_high_latitude_rule,
+    set_prayer_offsets,
+    set_rounding_policy,
     local_date,
     formatted_date,
     local_clock, 
     get_location,
+    refresh_location,
+    get_location_refresh_interval,
+    set_location_refresh_interval,
+    get_ip_location_enabled,
+    set_ip_location_enabled,
+    search_cities,
+    list_saved_locations,
+    save_location,
+    remove_location,
+    set_active_location,
     get_random_verse,
     add_prayer,
     get_prayer_data_by_date,
@@ -1,11 +1,14 @@
 use serde::{Serialize, Deserialize};
 
 // Location structure
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Location {
     pub ip: String,
     pub latitude: String,
     pub longitude: String,
+    // meters above the surrounding terrain, unknown for ip based locations
+    #[serde(default)]
+    pub elevation: Option<f64>,
     pub city: String,
     pub region: String,
     pub country: String,
@@ -13,6 +16,23 @@ pub struct Location {
     pub location: String,
 }
 
+// Place structure, explicit coordinates and timezone
+#[derive(Serialize, Deserialize)]
+pub struct Place {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub timezone: String,
+    #[serde(default)]
+    pub elevation: Option<f64>,
+}
+
+// Cached location structure, resolved_at is a unix timestamp in seconds
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CachedLocation {
+    pub location: Location,
+    pub resolved_at: i64,
+}
+
 // Today Verse structure
 #[derive(Serialize, Deserialize)]
 pub struct TodayVerse {
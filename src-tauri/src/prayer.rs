@@ -0,0 +1,933 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::astronomy::{darccos, darccot, dcos, dsin, dtan, fix_hour, julian_day, sun_position};
+use crate::hijri::HijriAdjustment;
+
+// Sun altitude at sunrise and sunset (refraction plus the solar semi-diameter)
+const SUNRISE_ANGLE: f64 = 0.833;
+
+// Horizon dip in degrees per square root of a meter of elevation
+const HORIZON_DIP: f64 = 0.0347;
+
+// Minutes after a prayer time during which it still counts as the current prayer
+const CURRENT_PRAYER_MINUTES: i64 = 35;
+
+// Ramadan is the ninth hijri month
+const RAMADAN: u32 = 9;
+
+// Latitude borrowed by the nearest latitude rule, and for every time when the sun does not rise or set
+const NEAREST_LATITUDE: f64 = 48.5;
+
+// Imsak is kept this many minutes before Fajr
+const IMSAK_MINUTES: f64 = 10.0;
+
+// Minutes after sunrise until the sun has risen a spear's length
+const ISHRAQ_MINUTES: f64 = 15.0;
+
+// Minutes before zawal when the sun is considered at its zenith and Duha ends
+const ZAWAL_MINUTES: f64 = 5.0;
+
+// Calculation method presets
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CalculationMethod {
+    #[default]
+    MuslimWorldLeague,
+    Isna,
+    Egypt,
+    UmmAlQura,
+    Karachi,
+    Tehran,
+    Kemenag,
+    Jakim,
+    Diyanet,
+}
+
+// Juristic school used for Asr
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AsrSchool {
+    // Shafi'i, Maliki and Hanbali
+    #[default]
+    Standard,
+    Hanafi,
+}
+
+impl AsrSchool {
+    // shadow length relative to the object's height, on top of the noon shadow
+    pub fn shadow_factor(&self) -> f64 {
+        match self {
+            AsrSchool::Standard => 1.0,
+            AsrSchool::Hanafi => 2.0,
+        }
+    }
+
+    // school number in the aladhan api
+    pub fn aladhan_id(&self) -> u32 {
+        match self {
+            AsrSchool::Standard => 0,
+            AsrSchool::Hanafi => 1,
+        }
+    }
+}
+
+// Rule for Fajr and Isha when twilight lasts through the night
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HighLatitudeRule {
+    MiddleOfTheNight,
+    OneSeventh,
+    #[default]
+    AngleBased,
+    NearestLatitude,
+}
+
+impl HighLatitudeRule {
+    // latitude adjustment number in the aladhan api, none for a rule it does not offer
+    pub fn aladhan_id(&self) -> Option<u32> {
+        match self {
+            HighLatitudeRule::MiddleOfTheNight => Some(1),
+            HighLatitudeRule::OneSeventh => Some(2),
+            HighLatitudeRule::AngleBased => Some(3),
+            HighLatitudeRule::NearestLatitude => None,
+        }
+    }
+}
+
+// Rounding applied to the published times
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingPolicy {
+    #[default]
+    Nearest,
+    Up,
+    Seconds,
+}
+
+impl RoundingPolicy {
+    // round hours after midnight to the policy precision, in seconds after midnight
+    pub fn round_seconds(&self, hours: f64) -> Option<u32> {
+        if !hours.is_finite() {
+            return None;
+        }
+
+        let seconds = fix_hour(hours) * 3600.0;
+        let rounded = match self {
+            RoundingPolicy::Nearest => (seconds / 60.0).round() * 60.0,
+            RoundingPolicy::Up => (seconds / 60.0).ceil() * 60.0,
+            RoundingPolicy::Seconds => seconds.round(),
+        };
+        Some(rounded as u32 % 86400)
+    }
+}
+
+// Minute offsets applied on top of the computed times
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct PrayerOffsets {
+    pub fajr: f64,
+    pub sunrise: f64,
+    pub dhuhr: f64,
+    pub asr: f64,
+    pub maghrib: f64,
+    pub isha: f64,
+}
+
+// Angles and intervals behind a calculation method
+#[derive(Serialize, Clone)]
+pub struct MethodParameters {
+    pub fajr_angle: f64,
+    pub isha_angle: Option<f64>,
+    pub isha_minutes: Option<f64>,
+    pub ramadan_isha_minutes: Option<f64>,
+    pub maghrib_angle: Option<f64>,
+    pub ihtiyat: PrayerOffsets,
+}
+
+// PrayerSettings structure, persisted as a single record in the settings table
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct PrayerSettings {
+    pub method: CalculationMethod,
+    pub asr_school: AsrSchool,
+    pub high_latitude_rule: HighLatitudeRule,
+    pub offsets: PrayerOffsets,
+    pub rounding: RoundingPolicy,
+}
+
+impl CalculationMethod {
+    pub const ALL: [CalculationMethod; 9] = [
+        CalculationMethod::MuslimWorldLeague,
+        CalculationMethod::Isna,
+        CalculationMethod::Egypt,
+        CalculationMethod::UmmAlQura,
+        CalculationMethod::Karachi,
+        CalculationMethod::Tehran,
+        CalculationMethod::Kemenag,
+        CalculationMethod::Jakim,
+        CalculationMethod::Diyanet,
+    ];
+
+    // human readable name of the authority
+    pub fn name(&self) -> &'static str {
+        match self {
+            CalculationMethod::MuslimWorldLeague => "Muslim World League",
+            CalculationMethod::Isna => "Islamic Society of North America",
+            CalculationMethod::Egypt => "Egyptian General Authority of Survey",
+            CalculationMethod::UmmAlQura => "Umm al-Qura University, Makkah",
+            CalculationMethod::Karachi => "University of Islamic Sciences, Karachi",
+            CalculationMethod::Tehran => "Institute of Geophysics, University of Tehran",
+            CalculationMethod::Kemenag => "Kementerian Agama Republik Indonesia",
+            CalculationMethod::Jakim => "Jabatan Kemajuan Islam Malaysia",
+            CalculationMethod::Diyanet => "Diyanet İşleri Başkanlığı, Turkey",
+        }
+    }
+
+    // angles, intervals and ihtiyat of the preset
+    pub fn parameters(&self) -> MethodParameters {
+        let angles = |fajr_angle: f64, isha_angle: f64| MethodParameters {
+            fajr_angle,
+            isha_angle: Some(isha_angle),
+            isha_minutes: None,
+            ramadan_isha_minutes: None,
+            maghrib_angle: None,
+            ihtiyat: PrayerOffsets::default(),
+        };
+
+        // MABIMS members add two minutes of ihtiyat to every prayer
+        let mabims = PrayerOffsets { fajr: 2.0, sunrise: -2.0, dhuhr: 2.0, asr: 2.0, maghrib: 2.0, isha: 2.0 };
+
+        match self {
+            CalculationMethod::MuslimWorldLeague => angles(18.0, 17.0),
+            CalculationMethod::Isna => angles(15.0, 15.0),
+            CalculationMethod::Egypt => angles(19.5, 17.5),
+            CalculationMethod::UmmAlQura => MethodParameters {
+                isha_angle: None,
+                isha_minutes: Some(90.0),
+                ramadan_isha_minutes: Some(120.0),
+                ..angles(18.5, 0.0)
+            },
+            CalculationMethod::Karachi => angles(18.0, 18.0),
+            CalculationMethod::Tehran => MethodParameters {
+                maghrib_angle: Some(4.5),
+                ..angles(17.7, 14.0)
+            },
+            CalculationMethod::Kemenag => MethodParameters { ihtiyat: mabims, ..angles(20.0, 18.0) },
+            CalculationMethod::Jakim => MethodParameters { ihtiyat: mabims, ..angles(20.0, 18.0) },
+            CalculationMethod::Diyanet => MethodParameters {
+                // temkin published by the Presidency of Religious Affairs
+                ihtiyat: PrayerOffsets { fajr: 0.0, sunrise: -7.0, dhuhr: 5.0, asr: 4.0, maghrib: 7.0, isha: 0.0 },
+                ..angles(18.0, 17.0)
+            },
+        }
+    }
+
+    // method number of the preset in the aladhan api
+    pub fn aladhan_id(&self) -> u32 {
+        match self {
+            CalculationMethod::MuslimWorldLeague => 3,
+            CalculationMethod::Isna => 2,
+            CalculationMethod::Egypt => 5,
+            CalculationMethod::UmmAlQura => 4,
+            CalculationMethod::Karachi => 1,
+            CalculationMethod::Tehran => 7,
+            CalculationMethod::Kemenag => 20,
+            CalculationMethod::Jakim => 17,
+            CalculationMethod::Diyanet => 13,
+        }
+    }
+}
+
+// Day on which the sun stays above or below the horizon
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PolarDay {
+    MidnightSun,
+    PolarNight,
+}
+
+impl PolarDay {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PolarDay::MidnightSun => "Midnight sun",
+            PolarDay::PolarNight => "Polar night",
+        }
+    }
+}
+
+// PrayerTimes structure, every time is in hours after local midnight
+#[derive(Clone)]
+pub struct PrayerTimes {
+    pub fajr: f64,
+    pub sunrise: f64,
+    pub noon: f64,
+    pub dhuhr: f64,
+    pub asr: f64,
+    pub sunset: f64,
+    pub maghrib: f64,
+    pub isha: f64,
+    pub fajr_adjusted: bool,
+    pub isha_adjusted: bool,
+    // set when every time comes from the nearest latitude because the sun does not rise or set
+    pub polar: Option<PolarDay>,
+}
+
+// DailySchedule structure, the five prayers with the voluntary and night times around them
+#[derive(Clone)]
+pub struct DailySchedule {
+    pub prayers: PrayerTimes,
+    pub imsak: f64,
+    pub ishraq: f64,
+    pub duha_start: f64,
+    pub duha_end: f64,
+    pub zawal: f64,
+    pub midnight_fajr: f64,
+    pub midnight_sunrise: f64,
+    pub last_third: f64,
+}
+
+// Nearest prayer structure, a prayer begun within the tolerance is the current one
+pub struct NearestPrayer {
+    pub nearest: &'static str,
+    pub next: &'static str,
+    pub next_time: NaiveTime,
+    pub until_next: Duration,
+    pub current: Option<&'static str>,
+}
+
+impl NearestPrayer {
+    // countdown to the next prayer as -H:MM:SS, or "Now" while a prayer is current
+    pub fn countdown(&self) -> String {
+        if self.current.is_some() {
+            return "Now".to_string();
+        }
+
+        format_countdown(self.until_next)
+    }
+}
+
+// format a countdown as -H:MM:SS
+pub fn format_countdown(duration: Duration) -> String {
+    let hours = duration.num_hours();
+    let minutes = (duration - Duration::hours(hours)).num_minutes();
+    let seconds = (duration - Duration::hours(hours) - Duration::minutes(minutes)).num_seconds();
+    format!("-{}:{:02}:{:02}", hours, minutes, seconds)
+}
+
+// Coordinates structure, elevation is in meters above the surrounding terrain
+#[derive(Clone, Copy)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub elevation: Option<f64>,
+}
+
+impl Coordinates {
+    // sun depression at sunrise and sunset, lowered by the dip of the horizon
+    pub fn rise_set_angle(&self) -> f64 {
+        match self.elevation {
+            Some(elevation) if elevation > 0.0 => SUNRISE_ANGLE + HORIZON_DIP * elevation.sqrt(),
+            _ => SUNRISE_ANGLE,
+        }
+    }
+}
+
+// Solar day for a single date and latitude
+#[derive(Clone, Copy)]
+struct SolarDay {
+    jd: f64,
+    latitude: f64,
+    rise_set_angle: f64,
+}
+
+impl SolarDay {
+    // time of solar noon for the given day portion
+    fn mid_day(&self, time: f64) -> f64 {
+        let eqt = sun_position(self.jd + time).equation_of_time;
+        fix_hour(12.0 - eqt)
+    }
+
+    // time when the sun is `angle` degrees below the horizon
+    fn sun_angle_time(&self, angle: f64, time: f64, ccw: bool) -> f64 {
+        let decl = sun_position(self.jd + time).declination;
+        let noon = self.mid_day(time);
+        let t = darccos((-dsin(angle) - dsin(decl) * dsin(self.latitude)) / (dcos(decl) * dcos(self.latitude))) / 15.0;
+
+        if ccw {
+            noon - t
+        } else {
+            noon + t
+        }
+    }
+
+    // time when an object's shadow is `factor` times its length plus the noon shadow
+    fn asr_time(&self, factor: f64, time: f64) -> f64 {
+        let decl = sun_position(self.jd + time).declination;
+        let angle = -darccot(factor + dtan((self.latitude - decl).abs()));
+        self.sun_angle_time(angle, time, false)
+    }
+
+    // whether the sun stays above or below the horizon all day, from its altitude at noon
+    fn polar_day(&self, times: &PrayerTimes) -> Option<PolarDay> {
+        if times.sunrise.is_finite() && times.sunset.is_finite() {
+            return None;
+        }
+
+        let decl = sun_position(self.jd + 0.5).declination;
+        if 90.0 - (self.latitude - decl).abs() > 0.0 {
+            Some(PolarDay::MidnightSun)
+        } else {
+            Some(PolarDay::PolarNight)
+        }
+    }
+
+    // prayer times in solar hours at the meridian, first guesses are refined once
+    fn prayer_times(&self, params: &MethodParameters, settings: &PrayerSettings) -> PrayerTimes {
+        let noon = self.mid_day(12.0 / 24.0);
+        let sunset = self.sun_angle_time(self.rise_set_angle, 18.0 / 24.0, false);
+
+        PrayerTimes {
+            fajr: self.sun_angle_time(params.fajr_angle, 5.0 / 24.0, true),
+            sunrise: self.sun_angle_time(self.rise_set_angle, 6.0 / 24.0, true),
+            noon,
+            dhuhr: noon,
+            asr: self.asr_time(settings.asr_school.shadow_factor(), 13.0 / 24.0),
+            sunset,
+            maghrib: match params.maghrib_angle {
+                Some(angle) => self.sun_angle_time(angle, 18.0 / 24.0, false),
+                None => sunset,
+            },
+            isha: match params.isha_angle {
+                Some(angle) => self.sun_angle_time(angle, 18.0 / 24.0, false),
+                None => f64::NAN,
+            },
+            fajr_adjusted: false,
+            isha_adjusted: false,
+            polar: None,
+        }
+    }
+}
+
+// prayer times of a date straight from the method angles, then after the high latitude
+// rule and the fixed isha interval, both without offsets; the hijri adjustment decides
+// which days fall in Ramadan
+fn prayer_time_stages(date: NaiveDate, coordinates: &Coordinates, utc_offset: f64, settings: &PrayerSettings, adjustment: &HijriAdjustment) -> (PrayerTimes, PrayerTimes) {
+    let params = settings.method.parameters();
+    let mut day = SolarDay {
+        jd: julian_day(date.year(), date.month(), date.day()) - coordinates.longitude / (15.0 * 24.0),
+        latitude: coordinates.latitude,
+        rise_set_angle: coordinates.rise_set_angle(),
+    };
+    let mut times = day.prayer_times(&params, settings);
+
+    // without a sunrise or sunset no time can be derived here, take the whole day from the nearest latitude
+    if let Some(polar) = day.polar_day(&times) {
+        day.latitude = NEAREST_LATITUDE.copysign(day.latitude);
+        times = day.prayer_times(&params, settings);
+        times.polar = Some(polar);
+    }
+
+    // shift from solar time at the meridian to the requested utc offset
+    times.shift(utc_offset - coordinates.longitude / 15.0);
+    let computed = times.clone();
+
+    apply_high_latitude_rule(&mut times, &day, &params, settings);
+
+    // methods with a fixed isha interval count it from maghrib
+    if let Some(minutes) = params.isha_minutes {
+        let minutes = match params.ramadan_isha_minutes {
+            Some(ramadan_minutes) if adjustment.to_hijri(date).month == RAMADAN => ramadan_minutes,
+            _ => minutes,
+        };
+        times.isha = times.maghrib + minutes / 60.0;
+    }
+
+    (computed, times)
+}
+
+// compute prayer times for a date, location and utc offset (hours)
+pub fn compute_prayer_times(date: NaiveDate, coordinates: &Coordinates, utc_offset: f64, settings: &PrayerSettings, adjustment: &HijriAdjustment) -> PrayerTimes {
+    let params = settings.method.parameters();
+    let (_, mut times) = prayer_time_stages(date, coordinates, utc_offset, settings, adjustment);
+
+    times.apply_offsets(&params.ihtiyat);
+    times.apply_offsets(&settings.offsets);
+    times
+}
+
+// Prayer diagnostics structure, the intermediate values behind the times of a date
+pub struct PrayerDiagnostics {
+    // sun declination in degrees and equation of time in minutes at solar noon
+    pub declination: f64,
+    pub equation_of_time: f64,
+    // latitude the times are computed at, the nearest latitude on polar days
+    pub latitude: f64,
+    pub rise_set_angle: f64,
+    // sun altitude when the shadow reaches the asr length
+    pub asr_altitude: f64,
+    pub night_length: f64,
+    pub parameters: MethodParameters,
+    // times from the method angles alone
+    pub computed: PrayerTimes,
+    // times after the high latitude rule and the fixed isha interval
+    pub adjusted: PrayerTimes,
+    // times after the method and user offsets
+    pub times: PrayerTimes,
+}
+
+// intermediate values behind the prayer times of a date
+pub fn prayer_diagnostics(date: NaiveDate, coordinates: &Coordinates, utc_offset: f64, settings: &PrayerSettings, adjustment: &HijriAdjustment) -> PrayerDiagnostics {
+    let (computed, adjusted) = prayer_time_stages(date, coordinates, utc_offset, settings, adjustment);
+    let jd = julian_day(date.year(), date.month(), date.day()) - coordinates.longitude / (15.0 * 24.0);
+    let sun = sun_position(jd + 0.5);
+    let latitude = match computed.polar {
+        Some(_) => NEAREST_LATITUDE.copysign(coordinates.latitude),
+        None => coordinates.latitude,
+    };
+
+    PrayerDiagnostics {
+        declination: sun.declination,
+        equation_of_time: sun.equation_of_time * 60.0,
+        latitude,
+        rise_set_angle: coordinates.rise_set_angle(),
+        asr_altitude: darccot(settings.asr_school.shadow_factor() + dtan((latitude - sun.declination).abs())),
+        night_length: 24.0 - (computed.sunset - computed.sunrise),
+        parameters: settings.method.parameters(),
+        times: compute_prayer_times(date, coordinates, utc_offset, settings, adjustment),
+        computed,
+        adjusted,
+    }
+}
+
+impl PrayerDiagnostics {
+    // values and every stage of each time, the times moved by a clock correction
+    pub fn to_json(&self, rounding: RoundingPolicy, correction: &dyn Fn(f64) -> f64) -> Value {
+        let (computed, adjusted, times) = (&self.computed, &self.adjusted, &self.times);
+        let params = &self.parameters;
+
+        // sun depression below the horizon behind each time, negative above it
+        let stages = [
+            ("Fajr", Some(params.fajr_angle), computed.fajr, adjusted.fajr, times.fajr, adjusted.fajr_adjusted),
+            ("Sunrise", Some(self.rise_set_angle), computed.sunrise, adjusted.sunrise, times.sunrise, false),
+            ("Dhuhr", None, computed.dhuhr, adjusted.dhuhr, times.dhuhr, false),
+            ("Asr", Some(-self.asr_altitude), computed.asr, adjusted.asr, times.asr, false),
+            ("Sunset", Some(self.rise_set_angle), computed.sunset, adjusted.sunset, times.sunset, false),
+            ("Maghrib", Some(params.maghrib_angle.unwrap_or(self.rise_set_angle)), computed.maghrib, adjusted.maghrib, times.maghrib, false),
+            ("Isha", params.isha_angle, computed.isha, adjusted.isha, times.isha, adjusted.isha_adjusted),
+        ];
+
+        let prayers: Vec<Value> = stages
+            .iter()
+            .map(|&(name, sun_angle, computed_time, adjusted_time, time, high_latitude)| {
+                let finite = |value: f64| if value.is_finite() { Some(value) } else { None };
+                let on_clock = |value: f64| format_time(value + correction(value), rounding);
+                let clock_correction = correction(time);
+                json!({
+                    "name": name,
+                    "sun_angle": sun_angle,
+                    "hour_angle": finite((computed_time - computed.noon) * 15.0),
+                    "computed": on_clock(computed_time),
+                    "high_latitude_adjusted": high_latitude,
+                    "adjusted": on_clock(adjusted_time),
+                    "offset_minutes": finite((time - adjusted_time) * 60.0),
+                    "clock_correction_minutes": clock_correction * 60.0,
+                    "time": on_clock(time),
+                })
+            })
+            .collect();
+
+        json!({
+            "declination": self.declination,
+            "equation_of_time_minutes": self.equation_of_time,
+            "latitude": self.latitude,
+            "polar": computed.polar,
+            "solar_noon": format_time(computed.noon + correction(computed.noon), rounding),
+            "rise_set_angle": self.rise_set_angle,
+            "asr_altitude": self.asr_altitude,
+            "night_hours": self.night_length,
+            "parameters": params,
+            "prayers": prayers,
+        })
+    }
+}
+
+// adjust fajr and isha when twilight never reaches the method angle or lasts
+// longer than the portion of the night allowed by the rule
+fn apply_high_latitude_rule(times: &mut PrayerTimes, day: &SolarDay, params: &MethodParameters, settings: &PrayerSettings) {
+    let rule = settings.high_latitude_rule;
+
+    // the night runs from sunset to the next sunrise, nothing can be adjusted without one
+    let night = 24.0 - (times.sunset - times.sunrise);
+    if !night.is_finite() {
+        return;
+    }
+
+    // borrow the twilight share of the night from the nearest latitude where it still exists
+    if rule == HighLatitudeRule::NearestLatitude && (!times.fajr.is_finite() || (params.isha_angle.is_some() && !times.isha.is_finite())) {
+        let reference = SolarDay { latitude: NEAREST_LATITUDE.copysign(day.latitude), ..*day }.prayer_times(params, settings);
+        let reference_night = 24.0 - (reference.sunset - reference.sunrise);
+
+        if !times.fajr.is_finite() {
+            times.fajr = times.sunrise - night * (reference.sunrise - reference.fajr) / reference_night;
+            times.fajr_adjusted = true;
+        }
+        if params.isha_angle.is_some() && !times.isha.is_finite() {
+            times.isha = times.sunset + night * (reference.isha - reference.sunset) / reference_night;
+            times.isha_adjusted = true;
+        }
+    }
+
+    let portion = |angle: f64| match rule {
+        HighLatitudeRule::MiddleOfTheNight => night / 2.0,
+        HighLatitudeRule::AngleBased => angle / 60.0 * night,
+        // one seventh also caps the twilight borrowed from the nearest latitude, where
+        // the method angles can be barely reached around the solstice
+        HighLatitudeRule::OneSeventh | HighLatitudeRule::NearestLatitude => night / 7.0,
+    };
+
+    let fajr_portion = portion(params.fajr_angle);
+    if !times.fajr.is_finite() || times.sunrise - times.fajr > fajr_portion {
+        times.fajr = times.sunrise - fajr_portion;
+        times.fajr_adjusted = true;
+    }
+
+    if let Some(angle) = params.isha_angle {
+        let isha_portion = portion(angle);
+        if !times.isha.is_finite() || times.isha - times.sunset > isha_portion {
+            times.isha = times.sunset + isha_portion;
+            times.isha_adjusted = true;
+        }
+    }
+}
+
+impl DailySchedule {
+    // build the schedule from today's times and tomorrow's (for the night that follows)
+    pub fn new(today: PrayerTimes, tomorrow: &PrayerTimes) -> DailySchedule {
+        let next_fajr = tomorrow.fajr + 24.0;
+        let next_sunrise = tomorrow.sunrise + 24.0;
+
+        DailySchedule {
+            imsak: today.fajr - IMSAK_MINUTES / 60.0,
+            ishraq: today.sunrise + ISHRAQ_MINUTES / 60.0,
+            duha_start: today.sunrise + ISHRAQ_MINUTES / 60.0,
+            duha_end: today.noon - ZAWAL_MINUTES / 60.0,
+            zawal: today.noon,
+            midnight_fajr: today.sunset + (next_fajr - today.sunset) / 2.0,
+            midnight_sunrise: today.sunset + (next_sunrise - today.sunset) / 2.0,
+            last_third: today.sunset + (next_fajr - today.sunset) * 2.0 / 3.0,
+            prayers: today,
+        }
+    }
+
+    // add a correction depending on the time itself to every time of the schedule
+    pub fn adjust(&mut self, correction: &dyn Fn(f64) -> f64) {
+        self.prayers.adjust(correction);
+        for time in [
+            &mut self.imsak,
+            &mut self.ishraq,
+            &mut self.duha_start,
+            &mut self.duha_end,
+            &mut self.zawal,
+            &mut self.midnight_fajr,
+            &mut self.midnight_sunrise,
+            &mut self.last_third,
+        ] {
+            *time += correction(*time);
+        }
+    }
+
+    // times when voluntary prayers are makruh, as (name, start, end)
+    pub fn forbidden_windows(&self) -> [(&'static str, f64, f64); 3] {
+        [
+            ("after_fajr", self.prayers.fajr, self.ishraq),
+            ("zawal", self.duha_end, self.zawal),
+            ("after_asr", self.prayers.asr, self.prayers.sunset),
+        ]
+    }
+
+    // every time of the day in chronological order
+    pub fn to_json(&self, rounding: RoundingPolicy) -> Value {
+        let prayers = &self.prayers;
+        let mut schedule = json!({
+            "Imsak": format_time(self.imsak, rounding),
+            "Fajr": format_time(prayers.fajr, rounding),
+            "Sunrise": format_time(prayers.sunrise, rounding),
+            "Ishraq": format_time(self.ishraq, rounding),
+            "Duha": {
+                "start": format_time(self.duha_start, rounding),
+                "end": format_time(self.duha_end, rounding),
+            },
+            "Zawal": format_time(self.zawal, rounding),
+            "Dhuhr": format_time(prayers.dhuhr, rounding),
+            "Asr": format_time(prayers.asr, rounding),
+            "Sunset": format_time(prayers.sunset, rounding),
+            "Maghrib": format_time(prayers.maghrib, rounding),
+            "Isha": format_time(prayers.isha, rounding),
+            "Midnight": {
+                "sunset_to_fajr": format_time(self.midnight_fajr, rounding),
+                "sunset_to_sunrise": format_time(self.midnight_sunrise, rounding),
+            },
+            "LastThird": format_time(self.last_third, rounding),
+        });
+
+        let timings = prayers.to_json(rounding);
+        for key in ["adjusted", "polar"] {
+            if let Some(value) = timings.get(key) {
+                schedule[key] = value.clone();
+            }
+        }
+
+        schedule
+    }
+}
+
+// format hours as HH:MM, or HH:MM:SS when rounding to seconds
+pub fn format_time(hours: f64, rounding: RoundingPolicy) -> String {
+    let seconds = match rounding.round_seconds(hours) {
+        Some(seconds) => seconds,
+        None => return "-----".to_string(),
+    };
+
+    if rounding == RoundingPolicy::Seconds {
+        format!("{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+    } else {
+        format!("{:02}:{:02}", seconds / 3600, seconds / 60 % 60)
+    }
+}
+
+// clock time of hours after midnight under the rounding policy
+pub fn clock_time(hours: f64, rounding: RoundingPolicy) -> Option<NaiveTime> {
+    rounding
+        .round_seconds(hours)
+        .and_then(|seconds| NaiveTime::from_num_seconds_from_midnight_opt(seconds, 0))
+}
+
+impl PrayerTimes {
+    // move every time by the given number of hours
+    fn shift(&mut self, hours: f64) {
+        self.fajr += hours;
+        self.sunrise += hours;
+        self.noon += hours;
+        self.dhuhr += hours;
+        self.asr += hours;
+        self.sunset += hours;
+        self.maghrib += hours;
+        self.isha += hours;
+    }
+
+    // add a correction depending on the time itself to every time, e.g. a daylight saving switch
+    pub fn adjust(&mut self, correction: &dyn Fn(f64) -> f64) {
+        for time in [
+            &mut self.fajr,
+            &mut self.sunrise,
+            &mut self.noon,
+            &mut self.dhuhr,
+            &mut self.asr,
+            &mut self.sunset,
+            &mut self.maghrib,
+            &mut self.isha,
+        ] {
+            *time += correction(*time);
+        }
+    }
+
+    // add minute offsets to every time except the astronomical noon and sunset
+    pub fn apply_offsets(&mut self, offsets: &PrayerOffsets) {
+        self.fajr += offsets.fajr / 60.0;
+        self.sunrise += offsets.sunrise / 60.0;
+        self.dhuhr += offsets.dhuhr / 60.0;
+        self.asr += offsets.asr / 60.0;
+        self.maghrib += offsets.maghrib / 60.0;
+        self.isha += offsets.isha / 60.0;
+    }
+
+    // the five daily prayers in order
+    pub fn fard_prayers(&self) -> [(&'static str, f64); 5] {
+        [
+            ("Fajr", self.fajr),
+            ("Dhuhr", self.dhuhr),
+            ("Asr", self.asr),
+            ("Maghrib", self.maghrib),
+            ("Isha", self.isha),
+        ]
+    }
+
+    // nearest and next of the five daily prayers at a clock time, prayers already passed
+    // today stand in for tomorrow's
+    pub fn nearest(&self, rounding: RoundingPolicy, current_time: NaiveTime) -> Option<NearestPrayer> {
+        let tolerance = Duration::minutes(CURRENT_PRAYER_MINUTES);
+        let mut result: Option<NearestPrayer> = None;
+        let mut nearest_diff = Duration::hours(24);
+
+        for (prayer, hours) in self.fard_prayers() {
+            // Round the prayer time with the saved policy
+            let prayer_time = match clock_time(hours, rounding) {
+                Some(prayer_time) => prayer_time,
+                None => continue,
+            };
+
+            // Time until the prayer, and its distance when it has just begun
+            let (until, diff) = if prayer_time > current_time {
+                (prayer_time - current_time, prayer_time - current_time)
+            } else {
+                let passed_time = current_time - prayer_time;
+                let until = Duration::hours(24) - passed_time;
+                (until, if passed_time <= tolerance { passed_time } else { until })
+            };
+            let current = prayer_time <= current_time && current_time - prayer_time <= tolerance;
+
+            let entry = result.get_or_insert(NearestPrayer {
+                nearest: prayer,
+                next: prayer,
+                next_time: prayer_time,
+                until_next: until,
+                current: None,
+            });
+            if diff < nearest_diff {
+                nearest_diff = diff;
+                entry.nearest = prayer;
+            }
+            if until < entry.until_next {
+                entry.next = prayer;
+                entry.next_time = prayer_time;
+                entry.until_next = until;
+            }
+            if current {
+                entry.current = Some(prayer);
+            }
+        }
+
+        result
+    }
+
+    // the five daily prayers in the same shape as the aladhan timings
+    pub fn to_json(&self, rounding: RoundingPolicy) -> Value {
+        let mut timings = json!({});
+        for (name, hours) in self.fard_prayers() {
+            timings[name] = json!(format_time(hours, rounding));
+        }
+
+        // flag the times that come from a high latitude rule
+        let adjusted: Vec<&str> = [("Fajr", self.fajr_adjusted), ("Isha", self.isha_adjusted)]
+            .iter()
+            .filter(|(_, adjusted)| *adjusted)
+            .map(|(name, _)| *name)
+            .collect();
+        if !adjusted.is_empty() {
+            timings["adjusted"] = json!(adjusted);
+        }
+        if let Some(polar) = self.polar {
+            timings["polar"] = json!(polar);
+        }
+
+        timings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Timelike;
+
+    use super::*;
+    use crate::hijri::MonthAdjustment;
+
+    // fajr, dhuhr, asr, maghrib and isha as aladhan returns them, from its PrayTimes
+    // algorithm with the default angle based adjustment, rounded to the minute
+    fn assert_matches_aladhan(date: (i32, u32, u32), coordinates: Coordinates, utc_offset: f64, settings: PrayerSettings, expected: [&str; 5]) {
+        let date = NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap();
+        let times = compute_prayer_times(date, &coordinates, utc_offset, &settings, &HijriAdjustment::default());
+
+        for ((prayer, hours), expected) in times.fard_prayers().iter().zip(expected) {
+            let expected = NaiveTime::parse_from_str(expected, "%H:%M").unwrap();
+            let expected = (expected.hour() * 60 + expected.minute()) as f64;
+            let difference = hours * 60.0 - expected;
+            assert!(difference.abs() <= 1.0, "{} on {} is {} minutes from aladhan", prayer, date, difference);
+        }
+    }
+
+    fn method(method: CalculationMethod) -> PrayerSettings {
+        PrayerSettings { method, ..PrayerSettings::default() }
+    }
+
+    const MECCA: Coordinates = Coordinates { latitude: 21.4225, longitude: 39.8262, elevation: None };
+    const JAKARTA: Coordinates = Coordinates { latitude: -6.2088, longitude: 106.8456, elevation: None };
+    const LONDON: Coordinates = Coordinates { latitude: 51.5074, longitude: -0.1278, elevation: None };
+    const NEW_YORK: Coordinates = Coordinates { latitude: 40.7128, longitude: -74.006, elevation: None };
+
+    #[test]
+    fn mecca_umm_al_qura() {
+        let settings = method(CalculationMethod::UmmAlQura);
+        assert_matches_aladhan((2024, 1, 15), MECCA, 3.0, settings.clone(), ["05:41", "12:30", "15:37", "17:59", "19:29"]);
+        assert_matches_aladhan((2024, 7, 1), MECCA, 3.0, settings, ["04:15", "12:25", "15:43", "19:07", "20:37"]);
+    }
+
+    #[test]
+    fn jakarta_kemenag() {
+        // aladhan publishes the Kemenag angles without the MABIMS ihtiyat
+        let offsets = PrayerOffsets { fajr: -2.0, sunrise: 2.0, dhuhr: -2.0, asr: -2.0, maghrib: -2.0, isha: -2.0 };
+        let settings = PrayerSettings { offsets, ..method(CalculationMethod::Kemenag) };
+        assert_matches_aladhan((2024, 1, 15), JAKARTA, 7.0, settings.clone(), ["04:24", "12:02", "15:27", "18:15", "19:30"]);
+        assert_matches_aladhan((2024, 7, 1), JAKARTA, 7.0, settings, ["04:40", "11:57", "15:19", "17:50", "19:04"]);
+    }
+
+    #[test]
+    fn london_muslim_world_league() {
+        let settings = method(CalculationMethod::MuslimWorldLeague);
+        assert_matches_aladhan((2024, 1, 15), LONDON, 0.0, settings.clone(), ["05:59", "12:10", "14:01", "16:20", "18:14"]);
+        assert_matches_aladhan((2024, 3, 15), LONDON, 0.0, settings, ["04:21", "12:09", "15:21", "18:06", "19:52"]);
+    }
+
+    #[test]
+    fn new_york_isna() {
+        let settings = method(CalculationMethod::Isna);
+        assert_matches_aladhan((2024, 1, 15), NEW_YORK, -5.0, settings.clone(), ["05:58", "12:05", "14:34", "16:53", "18:13"]);
+        assert_matches_aladhan((2024, 7, 1), NEW_YORK, -4.0, settings.clone(), ["03:50", "13:00", "17:00", "20:31", "22:10"]);
+
+        let hanafi = PrayerSettings { asr_school: AsrSchool::Hanafi, ..settings };
+        assert_matches_aladhan((2024, 7, 1), NEW_YORK, -4.0, hanafi, ["03:50", "13:00", "18:13", "20:31", "22:10"]);
+    }
+
+    #[test]
+    fn ramadan_isha_follows_the_hijri_adjustment() {
+        let settings = method(CalculationMethod::UmmAlQura);
+        let first_of_ramadan = NaiveDate::from_ymd_opt(2024, 3, 11).unwrap();
+        let isha_minutes = |adjustment: &HijriAdjustment| {
+            let times = compute_prayer_times(first_of_ramadan, &MECCA, 3.0, &settings, adjustment);
+            ((times.isha - times.maghrib) * 60.0).round()
+        };
+
+        // Ramadan announced a day later makes the published first of Ramadan the last of Sha'ban
+        let sighting = HijriAdjustment { days: 0, months: vec![MonthAdjustment { year: 1445, month: 9, days: 1 }] };
+        assert_eq!(isha_minutes(&HijriAdjustment::default()), 120.0);
+        assert_eq!(isha_minutes(&sighting), 90.0);
+    }
+
+    #[test]
+    fn polar_days_take_every_time_from_the_nearest_latitude() {
+        let tromso = Coordinates { latitude: 69.6489, longitude: 18.9551, elevation: None };
+        let settings = method(CalculationMethod::MuslimWorldLeague);
+
+        for (date, utc_offset, polar) in [((2024, 6, 21), 2.0, PolarDay::MidnightSun), ((2024, 12, 21), 1.0, PolarDay::PolarNight)] {
+            let date = NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap();
+            let times = compute_prayer_times(date, &tromso, utc_offset, &settings, &HijriAdjustment::default());
+            assert!(times.polar == Some(polar), "{} is not a {}", date, polar.name());
+
+            let order = [times.fajr, times.sunrise, times.dhuhr, times.asr, times.sunset, times.maghrib, times.isha];
+            assert!(order.iter().all(|time| time.is_finite()), "{} has an undefined time", date);
+            assert!(order.windows(2).all(|pair| pair[0] <= pair[1]), "{} times are out of order", date);
+        }
+
+        // a day with a sunrise and sunset keeps its own latitude
+        let date = NaiveDate::from_ymd_opt(2024, 3, 20).unwrap();
+        assert!(compute_prayer_times(date, &tromso, 1.0, &settings, &HijriAdjustment::default()).polar.is_none());
+    }
+
+    #[test]
+    fn nearest_latitude_is_capped_at_one_seventh_of_the_night() {
+        // aladhan with the one seventh adjustment, London never reaches 18 degrees in June
+        let settings = PrayerSettings { high_latitude_rule: HighLatitudeRule::NearestLatitude, ..method(CalculationMethod::MuslimWorldLeague) };
+        assert_matches_aladhan((2024, 6, 21), LONDON, 1.0, settings, ["03:40", "13:02", "17:25", "21:22", "22:25"]);
+    }
+}
@@ -0,0 +1,134 @@
+// Qibla direction, distance to the Kaaba and sun based qibla times
+
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::astronomy::{darccos, darcsin, darctan2, dcos, dsin, dtan, fix_angle, julian_day, sun_position};
+
+// Kaaba coordinates
+pub const KAABA_LATITUDE: f64 = 21.422487;
+pub const KAABA_LONGITUDE: f64 = 39.826206;
+
+// mean earth radius in km
+pub const EARTH_RADIUS: f64 = 6371.0088;
+
+// scan step in hours when searching for a sun azimuth
+const AZIMUTH_SCAN_HOURS: f64 = 0.25;
+
+// initial great circle bearing from true north, in degrees
+pub fn initial_bearing(latitude: f64, longitude: f64, to_latitude: f64, to_longitude: f64) -> f64 {
+    let delta = to_longitude - longitude;
+    let y = dsin(delta) * dcos(to_latitude);
+    let x = dcos(latitude) * dsin(to_latitude) - dsin(latitude) * dcos(to_latitude) * dcos(delta);
+
+    fix_angle(darctan2(y, x))
+}
+
+// central angle between two points, in degrees
+pub fn central_angle(latitude: f64, longitude: f64, to_latitude: f64, to_longitude: f64) -> f64 {
+    let cos_angle = dsin(latitude) * dsin(to_latitude) + dcos(latitude) * dcos(to_latitude) * dcos(to_longitude - longitude);
+
+    darccos(cos_angle.clamp(-1.0, 1.0))
+}
+
+// great circle distance in km
+pub fn distance(latitude: f64, longitude: f64, to_latitude: f64, to_longitude: f64) -> f64 {
+    central_angle(latitude, longitude, to_latitude, to_longitude).to_radians() * EARTH_RADIUS
+}
+
+// qibla bearing from true north
+pub fn qibla_bearing(latitude: f64, longitude: f64) -> f64 {
+    initial_bearing(latitude, longitude, KAABA_LATITUDE, KAABA_LONGITUDE)
+}
+
+// distance to the Kaaba in km
+pub fn kaaba_distance(latitude: f64, longitude: f64) -> f64 {
+    distance(latitude, longitude, KAABA_LATITUDE, KAABA_LONGITUDE)
+}
+
+// sun azimuth from true north and altitude above the horizon, at a julian day in UT
+pub fn sun_horizontal(jd: f64, latitude: f64, longitude: f64) -> (f64, f64) {
+    let sun = sun_position(jd);
+    let ut = (jd + 0.5).fract() * 24.0;
+    let hour_angle = 15.0 * (ut + longitude / 15.0 + sun.equation_of_time - 12.0);
+
+    let azimuth = darctan2(dsin(hour_angle), dcos(hour_angle) * dsin(latitude) - dtan(sun.declination) * dcos(latitude)) + 180.0;
+    let altitude = darcsin(dsin(latitude) * dsin(sun.declination) + dcos(latitude) * dcos(sun.declination) * dcos(hour_angle));
+
+    (fix_angle(azimuth), altitude)
+}
+
+// local hours of a date when the sun above the horizon stands at the given azimuth
+pub fn sun_azimuth_times(date: NaiveDate, latitude: f64, longitude: f64, utc_offset: f64, azimuth: f64) -> Vec<f64> {
+    let midnight = julian_day(date.year(), date.month(), date.day()) - utc_offset / 24.0;
+    let offset = |hours: f64| {
+        let (sun_azimuth, altitude) = sun_horizontal(midnight + hours / 24.0, latitude, longitude);
+        ((sun_azimuth - azimuth + 540.0).rem_euclid(360.0) - 180.0, altitude)
+    };
+
+    // scan the day and refine every crossing by bisection
+    let mut times = Vec::new();
+    let steps = (24.0 / AZIMUTH_SCAN_HOURS) as usize;
+    for step in 0..steps {
+        let (mut start, mut end) = (step as f64 * AZIMUTH_SCAN_HOURS, (step + 1) as f64 * AZIMUTH_SCAN_HOURS);
+        let (start_offset, _) = offset(start);
+        let (end_offset, _) = offset(end);
+
+        // skip the wrap around on the opposite side of the sky
+        if start_offset.signum() == end_offset.signum() || (start_offset - end_offset).abs() > 180.0 {
+            continue;
+        }
+
+        for _ in 0..30 {
+            let middle = (start + end) / 2.0;
+            if offset(middle).0.signum() == start_offset.signum() {
+                start = middle;
+            } else {
+                end = middle;
+            }
+        }
+
+        let time = (start + end) / 2.0;
+        if offset(time).1 > 0.0 {
+            times.push(time);
+        }
+    }
+
+    times
+}
+
+// dates and UT hours of the two yearly transits when the sun stands over the Kaaba
+pub fn sun_over_kaaba(year: i32) -> Vec<(NaiveDate, f64)> {
+    let transit = |date: NaiveDate| {
+        let jd = julian_day(date.year(), date.month(), date.day());
+        let eqt = sun_position(jd + 0.5).equation_of_time;
+        let ut = 12.0 - KAABA_LONGITUDE / 15.0 - eqt;
+        (ut, sun_position(jd + ut / 24.0).declination - KAABA_LATITUDE)
+    };
+
+    let mut transits = Vec::new();
+    let mut date = match NaiveDate::from_ymd_opt(year, 1, 1) {
+        Some(date) => date,
+        None => return transits,
+    };
+    let (mut previous_ut, mut previous_gap) = transit(date);
+
+    while date.year() == year {
+        let next = date + Duration::days(1);
+        let (ut, gap) = transit(next);
+
+        // keep the transit closest to the declination crossing
+        if previous_gap.signum() != gap.signum() {
+            if previous_gap.abs() <= gap.abs() {
+                transits.push((date, previous_ut));
+            } else if next.year() == year {
+                transits.push((next, ut));
+            }
+        }
+
+        date = next;
+        previous_ut = ut;
+        previous_gap = gap;
+    }
+
+    transits
+}
@@ -0,0 +1,123 @@
+// Yearly prayer timetable with hijri dates and csv export
+
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::hijri::{HijriAdjustment, HijriDate};
+use crate::prayer::{format_time, DailySchedule, RoundingPolicy};
+
+// Calendar of the timetable year
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TimetableCalendar {
+    #[default]
+    Gregorian,
+    Hijri,
+}
+
+impl TimetableCalendar {
+    // first and last gregorian dates of a year in this calendar
+    pub fn year_range(&self, year: i32, adjustment: &HijriAdjustment) -> Option<(NaiveDate, NaiveDate)> {
+        match self {
+            TimetableCalendar::Gregorian => Some((NaiveDate::from_ymd_opt(year, 1, 1)?, NaiveDate::from_ymd_opt(year, 12, 31)?)),
+            TimetableCalendar::Hijri => {
+                let start = adjustment.to_gregorian(&HijriDate { year, month: 1, day: 1 })?;
+                let next = adjustment.to_gregorian(&HijriDate { year: year + 1, month: 1, day: 1 })?;
+                Some((start, next - Duration::days(1)))
+            }
+        }
+    }
+}
+
+// Timetable row of a single day
+pub struct TimetableRow {
+    pub date: NaiveDate,
+    pub hijri: HijriDate,
+    pub schedule: DailySchedule,
+}
+
+// csv column names, in the order of `TimetableRow::csv_fields`
+const CSV_HEADER: [&str; 15] = [
+    "Date",
+    "Hijri Date",
+    "Hijri Month",
+    "Imsak",
+    "Fajr",
+    "Sunrise",
+    "Ishraq",
+    "Dhuhr",
+    "Asr",
+    "Sunset",
+    "Maghrib",
+    "Isha",
+    "Midnight",
+    "Last Third",
+    "Adjusted",
+];
+
+impl TimetableRow {
+    // schedule of the day with its gregorian and hijri dates
+    pub fn to_json(&self, rounding: RoundingPolicy) -> Value {
+        let mut entry = self.schedule.to_json(rounding);
+        entry["date"] = json!(self.date.format("%Y-%m-%d").to_string());
+        entry["hijri"] = self.hijri.to_json();
+        entry
+    }
+
+    fn csv_fields(&self, rounding: RoundingPolicy) -> Vec<String> {
+        let schedule = &self.schedule;
+        let prayers = &schedule.prayers;
+        let mut adjusted = Vec::new();
+        if let Some(polar) = prayers.polar {
+            adjusted.push(polar.name());
+        }
+        if prayers.fajr_adjusted {
+            adjusted.push("Fajr");
+        }
+        if prayers.isha_adjusted {
+            adjusted.push("Isha");
+        }
+
+        vec![
+            self.date.format("%Y-%m-%d").to_string(),
+            format!("{:02}-{:02}-{}", self.hijri.day, self.hijri.month, self.hijri.year),
+            self.hijri.month_name().to_string(),
+            format_time(schedule.imsak, rounding),
+            format_time(prayers.fajr, rounding),
+            format_time(prayers.sunrise, rounding),
+            format_time(schedule.ishraq, rounding),
+            format_time(prayers.dhuhr, rounding),
+            format_time(prayers.asr, rounding),
+            format_time(prayers.sunset, rounding),
+            format_time(prayers.maghrib, rounding),
+            format_time(prayers.isha, rounding),
+            format_time(schedule.midnight_fajr, rounding),
+            format_time(schedule.last_third, rounding),
+            adjusted.join(", "),
+        ]
+    }
+}
+
+// quote a csv field when it holds a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// timetable as csv with a header line
+pub fn timetable_csv(rows: &[TimetableRow], rounding: RoundingPolicy) -> String {
+    let mut csv = CSV_HEADER.join(",");
+    csv.push('\n');
+
+    for row in rows {
+        let fields: Vec<String> = row.csv_fields(rounding).iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+
+    csv
+}